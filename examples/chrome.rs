@@ -0,0 +1,32 @@
+use libftrace::*;
+
+#[traced(level = Info, fields(id))]
+fn process(id: usize) {
+    for step in 0..3 {
+        step_once(id, step);
+    }
+}
+
+#[traced(level = Debug)]
+fn step_once(id: usize, step: usize) {
+    debug!("processing step {} of {}", step, id);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+}
+
+fn main() {
+    // Open `trace.json` in `chrome://tracing` or https://ui.perfetto.dev/
+    libftrace::set_sink(ChromeSink::create("trace.json").unwrap());
+
+    let handles = (0..4)
+        .map(|id| {
+            std::thread::Builder::new()
+                .name(format!("worker-{id}"))
+                .spawn(move || process(id))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
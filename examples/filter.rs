@@ -18,7 +18,7 @@ fn process_user(user: User) {
 fn main() {
     let filter = libftrace::filter::from_default_env()
         .or_else(|err| {
-            eprintln!("could not parse filter: {err:?}");
+            eprintln!("could not parse filter: {}", err.render());
 
             libftrace::filter::parse("info")
         })
@@ -0,0 +1,16 @@
+use libftrace::*;
+
+#[traced(level = Info, fields(user = name))]
+fn greet(name: &str) {
+    info!("hello, {}!", name);
+}
+
+fn main() {
+    // Write all spans and events to `stderr`, instead of `stdout`.
+    libftrace::set_sink(PrettySink::stderr());
+
+    // Also write them to an in-memory buffer.
+    libftrace::add_sink(PrettySink::new(Vec::new()));
+
+    greet("John Doe");
+}
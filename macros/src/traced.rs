@@ -7,18 +7,28 @@ use syn::spanned::Spanned;
 use syn::*;
 
 mod kw {
+    syn::custom_keyword!(name);
+    syn::custom_keyword!(target);
     syn::custom_keyword!(level);
     syn::custom_keyword!(fields);
     syn::custom_keyword!(err);
     syn::custom_keyword!(ret);
+    syn::custom_keyword!(skip);
+    syn::custom_keyword!(skip_all);
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
+#[cfg_attr(not(feature = "enabled"), allow(dead_code))]
 struct TracedArgs {
+    skip: bool,
+    skip_args: Vec<Ident>,
+    skip_all: bool,
+    name: Option<LitStr>,
+    target: Option<LitStr>,
     level: Option<Level>,
     fields: Option<Fields>,
-    emit_error: Option<FormatMode>,
-    emit_return: Option<FormatMode>,
+    emit_error: Option<EmitArgs>,
+    emit_return: Option<EmitArgs>,
 }
 
 impl Parse for TracedArgs {
@@ -28,7 +38,32 @@ impl Parse for TracedArgs {
         while !input.is_empty() {
             let lookahead = input.lookahead1();
 
-            if lookahead.peek(kw::level) {
+            if lookahead.peek(kw::skip_all) {
+                let _ = input.parse::<kw::skip_all>()?;
+                args.skip_all = true;
+            } else if lookahead.peek(kw::skip) {
+                let _ = input.parse::<kw::skip>()?;
+
+                // A bare `skip` opts the function out of tracing entirely,
+                // while `skip(...)` only omits the given arguments.
+                if input.peek(syn::token::Paren) {
+                    let content;
+                    let _ = syn::parenthesized!(content in input);
+
+                    args.skip_args
+                        .extend(content.parse_terminated(Ident::parse_any, Token![,])?);
+                } else {
+                    args.skip = true;
+                }
+            } else if lookahead.peek(kw::name) {
+                let _ = input.parse::<kw::name>()?;
+                let _ = input.parse::<Token![=]>()?;
+                args.name = Some(input.parse()?);
+            } else if lookahead.peek(kw::target) {
+                let _ = input.parse::<kw::target>()?;
+                let _ = input.parse::<Token![=]>()?;
+                args.target = Some(input.parse()?);
+            } else if lookahead.peek(kw::level) {
                 args.level = Some(input.parse()?);
             } else if lookahead.peek(kw::fields) {
                 args.fields = Some(input.parse()?);
@@ -49,6 +84,107 @@ impl Parse for TracedArgs {
     }
 }
 
+impl TracedArgs {
+    /// Merges the arguments of a method with the arguments of its `impl`
+    /// block, where the arguments of the method take precedence. Skipped
+    /// arguments and fields of both are combined, except for fields of the
+    /// block which are redefined by the method.
+    fn merge(self, block: &TracedArgs) -> TracedArgs {
+        let fields = match (&block.fields, self.fields) {
+            (Some(block), Some(method)) => {
+                let mut fields = Punctuated::new();
+
+                for field in &block.0 {
+                    if !method.0.iter().any(|redefined| redefined.name.iter().eq(&field.name)) {
+                        fields.push(field.clone());
+                    }
+                }
+
+                fields.extend(method.0);
+
+                Some(Fields(fields))
+            }
+            (block, method) => method.or_else(|| block.clone()),
+        };
+
+        TracedArgs {
+            skip: self.skip,
+            skip_args: block.skip_args.iter().cloned().chain(self.skip_args).collect(),
+            skip_all: self.skip_all || block.skip_all,
+            name: self.name.or_else(|| block.name.clone()),
+            target: self.target.or_else(|| block.target.clone()),
+            level: self.level.or(block.level),
+            fields,
+            emit_error: self.emit_error.or(block.emit_error),
+            emit_return: self.emit_return.or(block.emit_return),
+        }
+    }
+
+    /// Gets all fields which should be attached to the span of the given
+    /// function.
+    ///
+    /// Unless skipped, every argument of the function is recorded as a field
+    /// of the same name, followed by all fields from `fields(...)`. Arguments
+    /// which are shadowed by an explicit field of the same name are omitted.
+    fn all_fields(&self, sig: &Signature) -> syn::Result<Vec<Field>> {
+        let idents = arg_idents(sig);
+
+        if let Some(unknown) = self.skip_args.iter().find(|skipped| !idents.contains(skipped)) {
+            return Err(Error::new(unknown.span(), format!("no argument named `{unknown}`")));
+        }
+
+        let explicit = self.fields.iter().flat_map(|fields| &fields.0);
+        let mut fields = Vec::new();
+
+        if !self.skip_all {
+            for ident in idents {
+                let shadowed = explicit
+                    .clone()
+                    .any(|field| field.name.len() == 1 && field.name[0] == ident);
+
+                if !shadowed && !self.skip_args.contains(&ident) {
+                    fields.push(Field {
+                        name: Punctuated::from_iter([ident]),
+                        value: None,
+                    });
+                }
+            }
+        }
+
+        fields.extend(explicit.cloned());
+
+        Ok(fields)
+    }
+}
+
+/// Collects all identifiers bound by the arguments of the given function,
+/// excluding the receiver.
+fn arg_idents(sig: &Signature) -> Vec<Ident> {
+    let mut idents = Vec::new();
+
+    for input in &sig.inputs {
+        if let FnArg::Typed(arg) = input {
+            pat_idents(&arg.pat, &mut idents);
+        }
+    }
+
+    idents
+}
+
+/// Collects all identifiers bound by the given argument pattern.
+fn pat_idents(pat: &Pat, idents: &mut Vec<Ident>) {
+    match pat {
+        Pat::Ident(pat) => idents.push(pat.ident.clone()),
+        Pat::Paren(pat) => pat_idents(&pat.pat, idents),
+        Pat::Reference(pat) => pat_idents(&pat.pat, idents),
+        Pat::Type(pat) => pat_idents(&pat.pat, idents),
+        Pat::Tuple(pat) => pat.elems.iter().for_each(|pat| pat_idents(pat, idents)),
+        Pat::TupleStruct(pat) => pat.elems.iter().for_each(|pat| pat_idents(pat, idents)),
+        Pat::Struct(pat) => pat.fields.iter().for_each(|field| pat_idents(&field.pat, idents)),
+        _ => {}
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum Level {
     Trace,
@@ -126,6 +262,23 @@ impl Parse for Field {
     }
 }
 
+impl Field {
+    /// Creates an expression which converts the value of the field into a
+    /// `Value`. If no value is given, the field refers to a variable of the
+    /// same name.
+    fn to_value(&self) -> proc_macro2::TokenStream {
+        let value = match &self.value {
+            Some(value) => quote! { #value },
+            None => {
+                let key = &self.name;
+                quote! { #key }
+            }
+        };
+
+        FormatMode::Debug.to_value(value)
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum FormatMode {
     #[default]
@@ -135,31 +288,452 @@ pub(crate) enum FormatMode {
 
 impl Parse for FormatMode {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        if !input.peek(syn::token::Paren) {
-            return Ok(Self::default());
-        }
-
-        let content;
-        let _ = syn::parenthesized!(content in input);
-
-        let mode = if let Some(ident) = content.parse::<Option<Ident>>()? {
+        let mode = if let Some(ident) = input.parse::<Option<Ident>>()? {
             match ident.to_string().as_str() {
                 "Debug" => FormatMode::Debug,
                 "Display" => FormatMode::Display,
                 _ => return Err(syn::Error::new(ident.span(), "expected either `Debug` or `Display`")),
             }
         } else {
-            return Err(syn::Error::new(content.span(), "expected either `Debug` or `Display`"));
+            return Err(syn::Error::new(input.span(), "expected either `Debug` or `Display`"));
         };
 
         Ok(mode)
     }
 }
 
+impl FormatMode {
+    /// Creates an expression which converts the given value into a `Value`.
+    ///
+    /// Primitive values keep their type, while all other values are formatted
+    /// using the implementation given by the mode. The conversion is spanned
+    /// to the value, so missing implementations are reported on the offending
+    /// value.
+    fn to_value(self, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self {
+            FormatMode::Debug => quote_spanned! { value.span() =>
+                {
+                    #[allow(unused_imports)]
+                    use ::libftrace::__private::{ViaDebug as _, ViaToValue as _};
+
+                    (&::libftrace::__private::DebugValue(&(#value))).__value()
+                }
+            },
+            FormatMode::Display => quote_spanned! { value.span() =>
+                {
+                    #[allow(unused_imports)]
+                    use ::libftrace::__private::{ViaDisplay as _, ViaToValue as _};
+
+                    (&::libftrace::__private::DisplayValue(&(#value))).__value()
+                }
+            },
+        }
+    }
+}
+
+/// Arguments of the `err` and `ret` arguments, such as `err(level = Warn,
+/// Display)`, which define the level of the emitted event and how the value is
+/// formatted.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EmitArgs {
+    pub(crate) level: Option<Level>,
+    pub(crate) mode: FormatMode,
+}
+
+impl Parse for EmitArgs {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut args = Self::default();
+
+        if !input.peek(syn::token::Paren) {
+            return Ok(args);
+        }
+
+        let content;
+        let _ = syn::parenthesized!(content in input);
+
+        while !content.is_empty() {
+            if content.peek(kw::level) {
+                args.level = Some(content.parse()?);
+            } else {
+                args.mode = content.parse()?;
+            }
+
+            if !content.is_empty() {
+                let _ = content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Defines how the result of a function is split into successful and failed
+/// values, for the `err` argument.
+#[cfg(feature = "enabled")]
+enum ReturnKind {
+    /// `Ok` values are successful, `Err` values are failures.
+    Result,
+
+    /// `Some` values are successful, `None` values are reported as misses.
+    Option,
+
+    /// `Continue` values are successful, `Break` values are failures.
+    ControlFlow,
+}
+
+#[cfg(feature = "enabled")]
+impl ReturnKind {
+    /// Determines the kind of the given return type, from the name of the
+    /// type. Since type aliases cannot be resolved, all types which aren't
+    /// named `Option` or `ControlFlow` are assumed to be results.
+    fn of(output: &ReturnType) -> Self {
+        let ReturnType::Type(_, ty) = output else {
+            return ReturnKind::Result;
+        };
+
+        let Type::Path(TypePath { path, .. }) = &**ty else {
+            return ReturnKind::Result;
+        };
+
+        match path.segments.last().map(|segment| segment.ident.to_string()).as_deref() {
+            Some("Option") => ReturnKind::Option,
+            Some("ControlFlow") => ReturnKind::ControlFlow,
+            _ => ReturnKind::Result,
+        }
+    }
+}
+
 pub(crate) fn traced(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = syn::parse_macro_input!(args as TracedArgs);
 
-    let input = parse_macro_input!(input as ItemFn);
+    let expanded = match parse_macro_input!(input as Item) {
+        Item::Fn(input) if args.skip => Ok(input.into_token_stream()),
+        Item::Fn(ItemFn {
+            sig: Signature {
+                constness: Some(constness),
+                ..
+            },
+            ..
+        }) => Err(Error::new_spanned(constness, "`#[traced]` cannot be applied to `const fn`s")),
+        Item::Fn(input) => {
+            let ident = &input.sig.ident;
+            let path = quote! { concat!(module_path!(), "::", stringify!(#ident)) };
+
+            expand_fn(&args, input, path.clone(), path)
+        }
+        Item::Impl(input) => expand_impl(&args, input),
+        item => Err(Error::new_spanned(
+            item,
+            "`#[traced]` can only be applied to functions and `impl` blocks",
+        )),
+    };
+
+    expanded.unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Expands `#[traced]` on an `impl` block, by applying it to every method in
+/// the block.
+///
+/// Methods can override individual arguments of the block with their own
+/// `#[traced]` attribute, or opt out entirely with `#[traced(skip)]`. Arguments skipped by
+/// the block only need to exist on some of its methods. The names of the
+/// spans include the `Self` type, as well as the trait, if any. Since filters
+/// can't express the trait, the targets of the spans only include the type
+/// named by `Self`, such that methods of trait implementations can still be
+/// filtered by their module.
+fn expand_impl(args: &TracedArgs, mut input: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    let self_name = type_name(&input.self_ty)?;
+    let self_target = type_target(&input.self_ty);
+    let trait_name = input.trait_.as_ref().map(|(_, path, _)| path_name(path));
+    let mut unused_skips = args.skip_args.clone();
+
+    for item in &mut input.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+
+        let idents = arg_idents(&method.sig);
+        unused_skips.retain(|skipped| !idents.contains(skipped));
+
+        let mut block_args = args.clone();
+        block_args.skip_args.retain(|skipped| idents.contains(skipped));
+
+        let (args, explicit) = match take_traced_attr(&mut method.attrs)? {
+            Some(args) => (args.merge(&block_args), true),
+            None => (block_args, false),
+        };
+
+        if args.skip {
+            continue;
+        }
+
+        // Spans can't be created in constant contexts, so `const fn`s are
+        // skipped, unless they were explicitly asked to be traced.
+        if let Some(constness) = method.sig.constness {
+            if explicit {
+                return Err(Error::new_spanned(constness, "`#[traced]` cannot be applied to `const fn`s"));
+            }
+
+            continue;
+        }
+
+        let ident = method.sig.ident.to_string();
+        let target = match &self_target {
+            Some(self_target) => quote! {
+                concat!(module_path!(), "::", #self_target, "::", #ident)
+            },
+            None => quote! {
+                concat!(module_path!(), "::", #ident)
+            },
+        };
+
+        let name = match &trait_name {
+            Some(trait_name) => quote! {
+                concat!("<", #self_name, " as ", #trait_name, ">::", #ident)
+            },
+            None => quote! {
+                concat!(#self_name, "::", #ident)
+            },
+        };
+
+        let input = ItemFn {
+            attrs: std::mem::take(&mut method.attrs),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        };
+
+        let defaultness = method.defaultness;
+
+        *method = syn::parse2(expand_fn(&args, input, name, target)?)?;
+        method.defaultness = defaultness;
+    }
+
+    if let Some(unknown) = unused_skips.first() {
+        return Err(Error::new(unknown.span(), format!("no method has an argument named `{unknown}`")));
+    }
+
+    Ok(input.into_token_stream())
+}
+
+/// Removes the `#[traced]` attribute from the given list of attributes, if any,
+/// returning the arguments which were given to it.
+fn take_traced_attr(attrs: &mut Vec<Attribute>) -> syn::Result<Option<TracedArgs>> {
+    let Some(idx) = attrs.iter().position(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "traced")
+    }) else {
+        return Ok(None);
+    };
+
+    match attrs.remove(idx).meta {
+        Meta::Path(_) => Ok(Some(TracedArgs::default())),
+        Meta::List(list) => list.parse_args().map(Some),
+        meta @ Meta::NameValue(_) => Err(Error::new_spanned(meta, "expected `#[traced(...)]`")),
+    }
+}
+
+/// Gets the name of the given type, as used in span names, as arguments to
+/// `concat!`. Named types are prefixed by the module path instead of the path
+/// they were written with, and don't include any generic arguments, since they
+/// usually are generic parameters of the `impl` block.
+fn type_name(ty: &Type) -> syn::Result<proc_macro2::TokenStream> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => {
+            let name = match path.segments.last() {
+                Some(segment) => segment.ident.to_string(),
+                None => path_name(path),
+            };
+
+            Ok(quote! { module_path!(), "::", #name })
+        }
+        Type::Group(group) => type_name(&group.elem),
+        Type::Paren(paren) => type_name(&paren.elem),
+        Type::Reference(reference) => {
+            let prefix = if reference.mutability.is_some() { "&mut " } else { "&" };
+            let elem = type_name(&reference.elem)?;
+
+            Ok(quote! { #prefix, #elem })
+        }
+        Type::Ptr(ptr) => {
+            let prefix = if ptr.mutability.is_some() { "*mut " } else { "*const " };
+            let elem = type_name(&ptr.elem)?;
+
+            Ok(quote! { #prefix, #elem })
+        }
+        Type::Slice(slice) => {
+            let elem = type_name(&slice.elem)?;
+
+            Ok(quote! { "[", #elem, "]" })
+        }
+        Type::Array(array) => {
+            let elem = type_name(&array.elem)?;
+            let len = array.len.to_token_stream().to_string();
+
+            Ok(quote! { "[", #elem, "; ", #len, "]" })
+        }
+        Type::Tuple(tuple) => {
+            let mut parts = Vec::new();
+
+            for (idx, elem) in tuple.elems.iter().enumerate() {
+                if idx > 0 {
+                    parts.push(quote! { ", " });
+                }
+
+                parts.push(type_name(elem)?);
+            }
+
+            if tuple.elems.len() == 1 {
+                parts.push(quote! { "," });
+            }
+
+            Ok(quote! { "(", #(#parts,)* ")" })
+        }
+        ty => Err(Error::new_spanned(
+            ty,
+            "`#[traced]` cannot be applied to `impl` blocks of this type",
+        )),
+    }
+}
+
+/// Gets the name of the type named by the given type, as used in span targets.
+/// References, pointers, slices and arrays are looked through, so the methods
+/// can be filtered by the type they refer to. Types which don't refer to a
+/// single named type, such as tuples, have no name.
+fn type_target(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last().map(|segment| segment.ident.to_string()),
+        Type::Group(group) => type_target(&group.elem),
+        Type::Paren(paren) => type_target(&paren.elem),
+        Type::Reference(reference) => type_target(&reference.elem),
+        Type::Ptr(ptr) => type_target(&ptr.elem),
+        Type::Slice(slice) => type_target(&slice.elem),
+        Type::Array(array) => type_target(&array.elem),
+        _ => None,
+    }
+}
+
+/// Gets the given path as a string, as it was written, including any generic
+/// arguments, such that implementations of the same generic trait can be told
+/// apart.
+fn path_name(path: &Path) -> String {
+    let mut name = String::new();
+
+    if path.leading_colon.is_some() {
+        name.push_str("::");
+    }
+
+    for (idx, segment) in path.segments.iter().enumerate() {
+        if idx > 0 {
+            name.push_str("::");
+        }
+
+        name.push_str(&segment.ident.to_string());
+
+        match &segment.arguments {
+            PathArguments::None => {}
+            PathArguments::AngleBracketed(args) => {
+                let args = args.args.iter().map(generic_arg_name).collect::<Vec<_>>();
+                name.push_str(&format!("<{}>", args.join(", ")));
+            }
+            PathArguments::Parenthesized(args) => {
+                let inputs = args.inputs.iter().map(type_string).collect::<Vec<_>>();
+                name.push_str(&format!("({})", inputs.join(", ")));
+
+                if let ReturnType::Type(_, ty) = &args.output {
+                    name.push_str(&format!(" -> {}", type_string(ty)));
+                }
+            }
+        }
+    }
+
+    name
+}
+
+/// Gets the given generic argument as a string, as it was written.
+fn generic_arg_name(arg: &GenericArgument) -> String {
+    match arg {
+        GenericArgument::Lifetime(lifetime) => lifetime.to_string(),
+        GenericArgument::Type(ty) => type_string(ty),
+        GenericArgument::AssocType(assoc) => format!("{} = {}", assoc.ident, type_string(&assoc.ty)),
+        arg => arg.to_token_stream().to_string(),
+    }
+}
+
+/// Gets the given type as a string, as it was written.
+fn type_string(ty: &Type) -> String {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path_name(path),
+        Type::Group(group) => type_string(&group.elem),
+        Type::Paren(paren) => format!("({})", type_string(&paren.elem)),
+        Type::Reference(reference) => {
+            let mut name = String::from("&");
+
+            if let Some(lifetime) = &reference.lifetime {
+                name.push_str(&format!("{lifetime} "));
+            }
+
+            if reference.mutability.is_some() {
+                name.push_str("mut ");
+            }
+
+            name + &type_string(&reference.elem)
+        }
+        Type::Ptr(ptr) => {
+            let prefix = if ptr.mutability.is_some() { "*mut " } else { "*const " };
+            format!("{prefix}{}", type_string(&ptr.elem))
+        }
+        Type::Slice(slice) => format!("[{}]", type_string(&slice.elem)),
+        Type::Array(array) => format!("[{}; {}]", type_string(&array.elem), array.len.to_token_stream()),
+        Type::Tuple(tuple) => {
+            let elems = tuple.elems.iter().map(type_string).collect::<Vec<_>>();
+
+            match elems.as_slice() {
+                [elem] => format!("({elem},)"),
+                elems => format!("({})", elems.join(", ")),
+            }
+        }
+        ty => ty.to_token_stream().to_string(),
+    }
+}
+
+/// Expands `#[traced]` while tracing is disabled, which leaves the function
+/// as-is, except for type-checking the values of all fields, without ever
+/// evaluating them.
+#[cfg(not(feature = "enabled"))]
+fn expand_fn(
+    args: &TracedArgs,
+    mut input: ItemFn,
+    _name: proc_macro2::TokenStream,
+    _target: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = args.all_fields(&input.sig)?;
+
+    let values = fields.iter().map(Field::to_value);
+    let check: Stmt = parse_quote! {
+        #[allow(clippy::needless_borrow)]
+        if false {
+            #(let _ = #values;)*
+        }
+    };
+
+    input.block.stmts.insert(0, check);
+
+    Ok(input.into_token_stream())
+}
+
+/// Expands `#[traced]` on a single function, where `name` and `target` are
+/// expressions for the default name and target of the span, which are used
+/// unless overridden.
+#[cfg(feature = "enabled")]
+fn expand_fn(
+    args: &TracedArgs,
+    input: ItemFn,
+    name: proc_macro2::TokenStream,
+    target: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
     let ItemFn { attrs, vis, sig, .. } = &input;
 
     let Signature {
@@ -217,20 +791,31 @@ pub(crate) fn traced(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
-    let block = build_block(&args, &input);
+    let fields = args.all_fields(sig)?;
+    let block = build_block(args, &input, &fields, name, target, fake_return_edge);
 
-    quote_spanned! { sig.span() =>
+    Ok(quote_spanned! { sig.span() =>
         #fn_signature {
-            #fake_return_edge
-            { #block }
+            #block
         }
-    }
-    .into()
+    })
 }
 
-fn build_block(args: &TracedArgs, input: &ItemFn) -> proc_macro2::TokenStream {
+#[cfg(feature = "enabled")]
+fn build_block(
+    args: &TracedArgs,
+    input: &ItemFn,
+    fields: &[Field],
+    name: proc_macro2::TokenStream,
+    target: proc_macro2::TokenStream,
+    fake_return_edge: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     let ItemFn { block, sig, .. } = &input;
-    let Signature { ident, .. } = sig;
+    let Signature { asyncness, .. } = sig;
+
+    // Statements of the function body, which are spliced into the generated
+    // closures, so single-expression bodies aren't wrapped in extra braces.
+    let stmts = &block.stmts;
 
     let level = if let Some(level) = &args.level {
         quote_spanned! { level.span() => ::libftrace::#level }
@@ -238,96 +823,171 @@ fn build_block(args: &TracedArgs, input: &ItemFn) -> proc_macro2::TokenStream {
         quote_spanned! { input.span() => ::libftrace::Level::Info }
     };
 
-    let fields = if let Some(fields) = &args.fields {
-        let mut tt = quote! {};
+    let name = match &args.name {
+        Some(name) => name.to_token_stream(),
+        None => name,
+    };
 
-        for field in &fields.0 {
-            let key = &field.name;
-            let value = if let Some(value) = &field.value {
-                quote! { #value }
-            } else {
-                quote! { #key }
-            };
+    let target = match &args.target {
+        Some(target) => target.to_token_stream(),
+        None => target,
+    };
+
+    let field_names = fields.iter().map(|field| {
+        let key = &field.name;
+
+        quote! { stringify!(#key) }
+    });
 
-            tt.extend(quote! {
-                .with_field(stringify!(#key), format!("{:?}", #value))
-            });
+    let fields = fields.iter().map(|field| {
+        let key = &field.name;
+        let value = field.to_value();
+
+        quote! {
+            .with_field(stringify!(#key), #value)
         }
+    });
+
+    // The metadata of the span is only created if the callsite is enabled, so
+    // the fields aren't formatted for disabled spans. Since `STATIC_MAX_LEVEL`
+    // is a constant, statically disabled spans are removed by the compiler.
+    let new_span = quote! {
+        static __CALLSITE: ::libftrace::Callsite = ::libftrace::Callsite::span(#target, #level, &[#(#field_names),*]);
+
+        let __span = if !::libftrace::STATIC_MAX_LEVEL.enables(#level) || __CALLSITE.interest().is_never() {
+            ::libftrace::Span::none()
+        } else {
+            ::libftrace::with_subscriber(|s| {
+                s.new_span(
+                    ::libftrace::SpanMetadata::new(#name, #level)
+                        .with_target(#target)
+                        .with_callsite(&__CALLSITE)
+                        #(#fields)*
+                )
+            })
+        }
+    };
+
+    // For `async fn`s, the span is only entered while the future is being
+    // polled, so it doesn't remain entered across `.await` points while other
+    // tasks run on the same thread. Once the future has completed, the span is
+    // entered once more, so any emitted `err` or `ret` events are placed
+    // within it.
+    let (enter_span_guard, output) = if asyncness.is_some() {
+        let enter = quote! {
+            let __output = ::libftrace::Instrument::instrument(
+                async move {
+                    #fake_return_edge
+                    #(#stmts)*
+                },
+                __span.clone(),
+            )
+            .await;
+
+            let __guard = __span.enter();
+        };
 
-        tt
+        (enter, quote! { __output })
     } else {
-        quote! {}
+        let enter = quote! {
+            #fake_return_edge
+
+            let __guard = __span.enter();
+        };
+
+        // The fake return statement is repeated within the closure, so the
+        // type of its output is known before the result is matched on.
+        (enter, quote! { (move || { #fake_return_edge #(#stmts)* })() })
     };
 
-    let target = quote! { concat!(module_path!(), "::", stringify!(#ident)) };
+    let err_level = match args.emit_error.and_then(|err| err.level) {
+        Some(err_level) => quote! { ::libftrace::#err_level },
+        None => quote! { ::libftrace::Level::Error },
+    };
 
-    let enter_span_guard = quote! {
-        let __guard = libftrace::with_subscriber(|s| {
-            s.enter_span(
-                libftrace::SpanMetadata::new(#target, #level)
-                    #fields
-            )
-        })
+    let ret_level = match args.emit_return.and_then(|ret| ret.level) {
+        Some(ret_level) => quote! { ::libftrace::#ret_level },
+        None => level.clone(),
     };
 
-    let err_event = match args.emit_error {
-        Some(FormatMode::Display) => quote! {
-            ::libftrace::error!(#target, error = format!("{e}"))
-        },
-        None | Some(FormatMode::Debug) => quote! {
-            ::libftrace::error!(#target, error = format!("{e:?}"))
-        },
+    // The pattern of successful values, along with the pattern of failed
+    // values, which is also used to reconstruct the failed value.
+    let err_value = args.emit_error.unwrap_or_default().mode.to_value(quote! { e });
+    let (ok, err, err_field) = match ReturnKind::of(&sig.output) {
+        ReturnKind::Result => (quote! { Ok }, quote! { Err(e) }, quote! { error = #err_value }),
+        ReturnKind::Option => (quote! { Some }, quote! { None }, quote! { miss = true }),
+        ReturnKind::ControlFlow => (
+            quote! { ::std::ops::ControlFlow::Continue },
+            quote! { ::std::ops::ControlFlow::Break(e) },
+            quote! { break_value = #err_value },
+        ),
     };
 
-    let ret_event = match args.emit_return {
-        Some(FormatMode::Display) => quote! {
-            ::libftrace::event!(level: #level, #target, ret = format!("{x}"))
-        },
-        None | Some(FormatMode::Debug) => quote! {
-            ::libftrace::event!(level: #level, #target, ret = format!("{x:?}"))
-        },
+    let err_event = quote! {
+        ::libftrace::event!(level: #err_level, "{}", #name, #err_field)
+    };
+
+    let ret_value = args.emit_return.unwrap_or_default().mode.to_value(quote! { x });
+
+    // Emit the return value as an event and attach it to the span, so it
+    // can be shown when the span is exited. The event is filtered by its own
+    // level, so it may be enabled even if the span isn't. If the span is
+    // disabled, the value is only formatted if the event is enabled.
+    let ret_event = quote! {
+        if __span.is_disabled() {
+            ::libftrace::event!(level: #ret_level, "{}", #name, ret = #ret_value)
+        } else {
+            let __ret: ::libftrace::Value = #ret_value;
+            __span.record_return(__ret.clone());
+
+            ::libftrace::event!(level: #ret_level, "{}", #name, ret = __ret)
+        }
     };
 
     let block_result_emit = match (args.emit_error, args.emit_return) {
         (Some(_), Some(_)) => quote! {
             #[allow(clippy::redundant_closure_call)]
-            match (move || #block)() {
+            match #output {
                 #[allow(clippy::unit_arg)]
-                Ok(x) => {
+                #ok(x) => {
                     #ret_event;
-                    Ok(x)
+                    #ok(x)
                 },
-                Err(e) => {
+                #err => {
                     #err_event;
-                    Err(e)
+                    #err
                 }
             }
         },
         (Some(_), None) => quote! {
             #[allow(clippy::redundant_closure_call)]
-            match (move || #block)() {
+            match #output {
                 #[allow(clippy::unit_arg)]
-                Ok(x) => Ok(x),
-                Err(e) => {
+                #ok(x) => #ok(x),
+                #err => {
                     #err_event;
-                    Err(e)
+                    #err
                 }
             }
         },
         (None, Some(_)) => quote! {
             #[allow(clippy::redundant_closure_call)]
-            let x = (move || #block)();
+            let x = #output;
             #ret_event;
 
             x
         },
+        (None, None) if asyncness.is_some() => quote! {
+            __output
+        },
         (None, None) => quote! {
             #block
         },
     };
 
     quote! {
-        #enter_span_guard;
+        #new_span;
+        #enter_span_guard
 
         #block_result_emit
     }
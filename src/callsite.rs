@@ -0,0 +1,168 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{Level, with_subscriber};
+
+/// Static metadata of a single place in the source code which creates spans or
+/// events, such as a `#[traced]` function or an [`event!`] invocation.
+///
+/// Callsites are created by the macros, once per invocation. They cache whether
+/// the current filter is interested in the spans or events created by them, so
+/// callsites which are disabled by the filter only cost a single atomic load.
+/// The cached interest is recomputed whenever the filter changes.
+///
+/// [`event!`]: crate::event!
+pub struct Callsite {
+    target: &'static str,
+    kind: CallsiteKind,
+    level: Level,
+    fields: &'static [&'static str],
+    interest: AtomicU8,
+}
+
+/// Defines whether a [`Callsite`] creates spans or events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallsiteKind {
+    Span,
+    Event,
+}
+
+/// Defines whether the current filter is interested in a [`Callsite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    /// The filter never enables anything created by the callsite.
+    Never,
+
+    /// The filter may enable items created by the callsite, depending on their
+    /// field values or the spans they are created within.
+    Sometimes,
+
+    /// The filter always enables everything created by the callsite.
+    Always,
+}
+
+impl Interest {
+    /// Determines whether the filter never enables the callsite.
+    pub fn is_never(&self) -> bool {
+        *self == Interest::Never
+    }
+
+    /// Determines whether the filter always enables the callsite.
+    pub fn is_always(&self) -> bool {
+        *self == Interest::Always
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            NEVER => Some(Interest::Never),
+            SOMETIMES => Some(Interest::Sometimes),
+            ALWAYS => Some(Interest::Always),
+            _ => None,
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            Interest::Never => NEVER,
+            Interest::Sometimes => SOMETIMES,
+            Interest::Always => ALWAYS,
+        }
+    }
+}
+
+const UNREGISTERED: u8 = 0;
+const NEVER: u8 = 1;
+const SOMETIMES: u8 = 2;
+const ALWAYS: u8 = 3;
+
+/// All callsites which have been hit at least once, so their interest can be
+/// recomputed when the filter changes.
+static REGISTRY: Mutex<Vec<&'static Callsite>> = Mutex::new(Vec::new());
+
+impl Callsite {
+    /// Creates a new [`Callsite`] for spans with the given target, level and
+    /// field names.
+    pub const fn span(target: &'static str, level: Level, fields: &'static [&'static str]) -> Self {
+        Self::new(target, CallsiteKind::Span, level, fields)
+    }
+
+    /// Creates a new [`Callsite`] for events with the given target, level and
+    /// field names.
+    pub const fn event(target: &'static str, level: Level, fields: &'static [&'static str]) -> Self {
+        Self::new(target, CallsiteKind::Event, level, fields)
+    }
+
+    const fn new(target: &'static str, kind: CallsiteKind, level: Level, fields: &'static [&'static str]) -> Self {
+        Self {
+            target,
+            kind,
+            level,
+            fields,
+            interest: AtomicU8::new(UNREGISTERED),
+        }
+    }
+
+    /// Gets the target of all spans or events created by the callsite.
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+
+    /// Gets whether the callsite creates spans or events.
+    pub fn kind(&self) -> CallsiteKind {
+        self.kind
+    }
+
+    /// Gets the level of all spans or events created by the callsite.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Gets the names of all fields attached to spans or events created by the
+    /// callsite.
+    pub fn fields(&self) -> &'static [&'static str] {
+        self.fields
+    }
+
+    /// Gets the interest of the current filter in the callsite.
+    ///
+    /// The interest is computed the first time the callsite is hit, after
+    /// which it is cached until the filter changes.
+    #[inline]
+    pub fn interest(&'static self) -> Interest {
+        match Interest::from_u8(self.interest.load(Ordering::Acquire)) {
+            Some(interest) => interest,
+            None => self.register(),
+        }
+    }
+
+    #[cold]
+    fn register(&'static self) -> Interest {
+        let mut registry = REGISTRY.lock().unwrap_or_else(|err| err.into_inner());
+
+        // Another thread may have registered the callsite while this thread was
+        // waiting for the lock.
+        if let Some(interest) = Interest::from_u8(self.interest.load(Ordering::Acquire)) {
+            return interest;
+        }
+
+        registry.push(self);
+        self.recompute()
+    }
+
+    fn recompute(&self) -> Interest {
+        let interest = with_subscriber(|subscriber| subscriber.callsite_interest(self));
+        self.interest.store(interest.as_u8(), Ordering::Release);
+
+        interest
+    }
+}
+
+/// Recomputes the interest of all registered callsites, which must be done
+/// whenever the filter of the global subscriber changes.
+pub(crate) fn rebuild_interest() {
+    let registry = REGISTRY.lock().unwrap_or_else(|err| err.into_inner());
+
+    for callsite in registry.iter() {
+        callsite.recompute();
+    }
+}
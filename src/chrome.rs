@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::json::{write_str, write_value};
+use crate::{Context, EventMetadata, Sink, SpanMetadata, Value};
+
+/// A [`Sink`] which writes spans and events in the [Chrome Trace Event
+/// format], which can be opened in `chrome://tracing` or the [Perfetto UI].
+///
+/// Spans are written as complete (`X`) events once they are closed, spanning
+/// from the moment they were entered until they were closed. Events are
+/// written as instant (`i`) events. The fields of spans and events are written
+/// as the arguments of the trace event.
+///
+/// The output is written as a JSON array, which is never closed, since the
+/// sink cannot know when the program exits. Both `chrome://tracing` and
+/// Perfetto accept unterminated arrays.
+///
+/// ```no_run
+/// let sink = libftrace::ChromeSink::create("trace.json").unwrap();
+///
+/// libftrace::set_sink(sink);
+/// ```
+///
+/// [Chrome Trace Event format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+/// [Perfetto UI]: https://ui.perfetto.dev/
+pub struct ChromeSink<W: Write + Send> {
+    state: Mutex<ChromeState<W>>,
+    origin: Instant,
+    pid: u32,
+}
+
+struct ChromeState<W> {
+    writer: W,
+
+    /// Whether any trace events have been written yet.
+    started: bool,
+
+    /// IDs of all threads which have had their name written.
+    named_threads: HashSet<u64>,
+}
+
+impl<W: Write + Send> ChromeSink<W> {
+    /// Creates a new [`ChromeSink`] which writes to the given writer.
+    ///
+    /// All timestamps in the trace are relative to the creation of the sink.
+    pub fn new(writer: W) -> Self {
+        Self {
+            state: Mutex::new(ChromeState {
+                writer,
+                started: false,
+                named_threads: HashSet::new(),
+            }),
+            origin: Instant::now(),
+            pid: std::process::id(),
+        }
+    }
+
+    /// Gets the number of microseconds since the sink was created, until the
+    /// given instant.
+    fn timestamp(&self, at: Instant) -> f64 {
+        at.saturating_duration_since(self.origin).as_secs_f64() * 1_000_000.0
+    }
+
+    fn write_event(&self, thread_id: u64, cx: &Context<'_>, event: &TraceEvent<'_>) -> std::io::Result<()> {
+        let mut buf = Vec::with_capacity(256);
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        buf.extend_from_slice(if state.started { b",\n" } else { b"[\n" });
+        state.started = true;
+
+        // Name each thread the first time it's seen, so the timeline shows the
+        // thread names instead of their IDs. Spans may be closed on a different
+        // thread than they were entered on, so threads are only named when the
+        // event is written from the thread itself.
+        if thread_id == cx.thread_id && state.named_threads.insert(thread_id) {
+            if let Some(name) = std::thread::current().name() {
+                write!(
+                    buf,
+                    "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{thread_id},\"args\":{{\"name\":",
+                    self.pid
+                )?;
+                write_str(&mut buf, name)?;
+                buf.extend_from_slice(b"}},\n");
+            }
+        }
+
+        event.write_to(&mut buf, self.pid, thread_id)?;
+
+        state.writer.write_all(&buf)?;
+        state.writer.flush()
+    }
+}
+
+impl ChromeSink<std::fs::File> {
+    /// Creates a new [`ChromeSink`] which writes to the file at the given path.
+    ///
+    /// If the file already exists, it is truncated.
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(std::fs::File::create(path)?))
+    }
+}
+
+impl<W: Write + Send> Sink for ChromeSink<W> {
+    fn enter_span(&self, _span: &SpanMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        // Spans are written as complete events, once they have been closed.
+        Ok(())
+    }
+
+    fn exit_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        let entered_at = span.entered_at().unwrap_or_else(Instant::now);
+        let thread_id = span.thread_id().unwrap_or(cx.thread_id);
+
+        self.write_event(thread_id, cx, &TraceEvent {
+            name: span.name,
+            phase: "X",
+            level: span.level.as_str(),
+            timestamp: self.timestamp(entered_at),
+            duration: Some(span.elapsed()),
+            args: span.fields().collect(),
+            return_value: span.return_value(),
+        })
+    }
+
+    fn event(&self, event: &EventMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.write_event(cx.thread_id, cx, &TraceEvent {
+            name: &event.message,
+            phase: "i",
+            level: event.level.as_str(),
+            timestamp: self.timestamp(Instant::now()),
+            duration: None,
+            args: event.fields().collect(),
+            return_value: None,
+        })
+    }
+}
+
+/// A single event in the Chrome Trace Event format.
+struct TraceEvent<'a> {
+    name: &'a str,
+    phase: &'static str,
+    level: &'static str,
+    timestamp: f64,
+    duration: Option<Duration>,
+    args: Vec<(&'static str, &'a Value)>,
+    return_value: Option<&'a Value>,
+}
+
+impl TraceEvent<'_> {
+    fn write_to(&self, f: &mut dyn Write, pid: u32, tid: u64) -> std::io::Result<()> {
+        write!(f, "{{\"name\":")?;
+        write_str(f, self.name)?;
+
+        write!(f, ",\"cat\":\"{}\",\"ph\":\"{}\"", self.level, self.phase)?;
+        write!(f, ",\"ts\":{:.3},\"pid\":{pid},\"tid\":{tid}", self.timestamp)?;
+
+        if let Some(duration) = self.duration {
+            write!(f, ",\"dur\":{:.3}", duration.as_secs_f64() * 1_000_000.0)?;
+        }
+
+        // Instant events are scoped to the thread they were emitted on.
+        if self.phase == "i" {
+            write!(f, ",\"s\":\"t\"")?;
+        }
+
+        write!(f, ",\"args\":{{")?;
+        for (idx, (key, value)) in self.args.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ",")?;
+            }
+
+            write_str(f, key)?;
+            write!(f, ":")?;
+            write_value(f, value)?;
+        }
+
+        if let Some(value) = self.return_value {
+            if !self.args.is_empty() {
+                write!(f, ",")?;
+            }
+
+            write!(f, "\"return\":")?;
+            write_value(f, value)?;
+        }
+
+        write!(f, "}}}}")
+    }
+}
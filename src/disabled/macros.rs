@@ -1,10 +1,111 @@
+// While tracing is disabled, the macros below still type-check their
+// arguments, without ever evaluating them, so tracing calls don't rot until
+// tracing is enabled again.
+
 /// Creates a new event in the current span.
 ///
 /// The event macro is invoked with a [`crate::Level`], along with a message.
 /// The message may be a format string, followed by zero-or-more arguments.
+///
+/// The target of the event defaults to the path of the module which the macro
+/// was invoked from. To override it, add the `target` argument before the
+/// message, such as `event!(level: Level::Info, target: "http", "...")`. Both
+/// the level and the target must be constant expressions. To emit an event
+/// with a level which is only known at runtime, pass an [`crate::EventMetadata`]
+/// to [`crate::Subscriber::event`] instead.
 #[macro_export]
 macro_rules! event {
-    ($($args:tt)*) => {};
+    (@event $target:expr, $level:expr, $fmt:expr, $( $key:ident = $value:expr ),+) => {
+        $crate::event!(
+            @emit $target, $level, [$($key),+],
+            $crate::EventMetadata::new(format!($fmt), $level)
+                .with_target($target)
+                $(
+                    .with_field(stringify!($key), $crate::__field_value!($value))
+                )*
+        )
+    };
+    (@event $target:expr, $level:expr, $fmt:expr, $($args:expr)*, $( $key:ident = $value:expr ),+) => {
+        $crate::event!(
+            @emit $target, $level, [$($key),+],
+            $crate::EventMetadata::new(format!($fmt, $($args)*), $level)
+                .with_target($target)
+                $(
+                    .with_field(stringify!($key), $crate::__field_value!($value))
+                )*
+        )
+    };
+    (@event $target:expr, $level:expr, $fmt:expr) => {
+        $crate::event!(
+            @emit $target, $level, [],
+            $crate::EventMetadata::new(format!($fmt), $level).with_target($target)
+        )
+    };
+    (@event $target:expr, $level:expr, $fmt:expr, $($args:tt)*) => {
+        $crate::event!(
+            @emit $target, $level, [],
+            $crate::EventMetadata::new(format!($fmt, $($args)*), $level).with_target($target)
+        )
+    };
+    (@emit $target:expr, $level:expr, [$($key:ident),*], $metadata:expr) => {
+        if false {
+            let _ = $metadata;
+        }
+    };
+    (target: $target:expr, level: $level:expr, $($args:tt)*) => {
+        $crate::event!(@event $target, $level, $($args)*)
+    };
+    (level: $level:expr, target: $target:expr, $($args:tt)*) => {
+        $crate::event!(@event $target, $level, $($args)*)
+    };
+    (level: $level:expr, $($args:tt)*) => {
+        $crate::event!(@event module_path!(), $level, $($args)*)
+    };
+}
+
+/// Converts the value of a field into a [`crate::Value`], keeping the type of
+/// primitive values and formatting all other values using `Display`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_value {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaDisplay as _, ViaToValue as _};
+
+        (&$crate::__private::DisplayValue(&$value)).__value()
+    }};
+}
+
+/// Creates a new span, which can be entered using the returned [`crate::Span`].
+///
+/// The span macro is invoked with a [`crate::Level`] and the name of the span,
+/// followed by zero-or-more fields, such as
+/// `span!(Level::Debug, "parse", file = path.display())`.
+///
+/// The target of the span, which is matched by filters, defaults to the path
+/// of the module which the macro was invoked from. To override it, add the
+/// `target` argument before the level, such as
+/// `span!(target: "db", Level::Debug, "query")`. The level, name and target
+/// must all be constant expressions.
+///
+/// If the span is disabled, either statically or by the filter, a disabled
+/// span is returned, which never emits anything.
+#[macro_export]
+macro_rules! span {
+    (target: $target:expr, $level:expr, $name:expr $(, $key:ident = $value:expr )* $(,)?) => {{
+        if false {
+            let _ = $crate::SpanMetadata::new($name, $level)
+                .with_target($target)
+                $(
+                    .with_field(stringify!($key), $crate::__field_value!($value))
+                )*;
+        }
+
+        $crate::Span::none()
+    }};
+    ($level:expr, $name:expr $(, $key:ident = $value:expr )* $(,)?) => {
+        $crate::span!(target: module_path!(), $level, $name $(, $key = $value )*)
+    };
 }
 
 /// Creates a new trace-level event in the current span.
@@ -16,7 +117,9 @@ macro_rules! event {
 /// [crate]: crate#macros
 #[macro_export]
 macro_rules! trace {
-    ($($args:tt)*) => {};
+    ($($args:tt)*) => {
+        $crate::event!(level: $crate::Level::Trace, $($args)*);
+    };
 }
 
 /// Creates a new debug-level event in the current span.
@@ -28,7 +131,9 @@ macro_rules! trace {
 /// [crate]: crate#macros
 #[macro_export]
 macro_rules! debug {
-    ($($args:tt)*) => {};
+    ($($args:tt)*) => {
+        $crate::event!(level: $crate::Level::Debug, $($args)*);
+    };
 }
 
 /// Creates a new info-level event in the current span.
@@ -40,7 +145,9 @@ macro_rules! debug {
 /// [crate]: crate#macros
 #[macro_export]
 macro_rules! info {
-    ($($args:tt)*) => {};
+    ($($args:tt)*) => {
+        $crate::event!(level: $crate::Level::Info, $($args)*);
+    };
 }
 
 /// Creates a new warning-level event in the current span.
@@ -52,7 +159,9 @@ macro_rules! info {
 /// [crate]: crate#macros
 #[macro_export]
 macro_rules! warning {
-    ($($args:tt)*) => {};
+    ($($args:tt)*) => {
+        $crate::event!(level: $crate::Level::Warn, $($args)*);
+    };
 }
 
 /// Creates a new error-level event in the current span.
@@ -64,5 +173,7 @@ macro_rules! warning {
 /// [crate]: crate#macros
 #[macro_export]
 macro_rules! error {
-    ($($args:tt)*) => {};
+    ($($args:tt)*) => {
+        $crate::event!(level: $crate::Level::Error, $($args)*);
+    };
 }
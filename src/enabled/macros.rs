@@ -2,37 +2,119 @@
 ///
 /// The event macro is invoked with a [`crate::Level`], along with a message.
 /// The message may be a format string, followed by zero-or-more arguments.
+///
+/// The target of the event defaults to the path of the module which the macro
+/// was invoked from. To override it, add the `target` argument before the
+/// message, such as `event!(level: Level::Info, target: "http", "...")`. Both
+/// the level and the target must be constant expressions. To emit an event
+/// with a level which is only known at runtime, pass an [`crate::EventMetadata`]
+/// to [`crate::Subscriber::event`] instead.
 #[macro_export]
 macro_rules! event {
-    (level: $level:expr, $fmt:expr, $( $key:ident = $value:expr ),+) => {
-        $crate::with_subscriber(|s| {
-            s.event(
-                $crate::EventMetadata::new(format!($fmt), $level)
+    (@event $target:expr, $level:expr, $fmt:expr, $( $key:ident = $value:expr ),+) => {
+        $crate::event!(
+            @emit $target, $level, [$($key),+],
+            $crate::EventMetadata::new(format!($fmt), $level)
+                .with_target($target)
                 $(
-                    .with_field(stringify!($key), $value)
+                    .with_field(stringify!($key), $crate::__field_value!($value))
                 )*
-            );
-        });
+        )
     };
-    (level: $level:expr, $fmt:expr, $($args:expr)*, $( $key:ident = $value:expr ),+) => {
-        $crate::with_subscriber(|s| {
-            s.event(
-                $crate::EventMetadata::new(format!($fmt, $($args)*), $level)
+    (@event $target:expr, $level:expr, $fmt:expr, $($args:expr)*, $( $key:ident = $value:expr ),+) => {
+        $crate::event!(
+            @emit $target, $level, [$($key),+],
+            $crate::EventMetadata::new(format!($fmt, $($args)*), $level)
+                .with_target($target)
                 $(
-                    .with_field(stringify!($key), $value)
+                    .with_field(stringify!($key), $crate::__field_value!($value))
                 )*
-            );
-        });
+        )
+    };
+    (@event $target:expr, $level:expr, $fmt:expr) => {
+        $crate::event!(
+            @emit $target, $level, [],
+            $crate::EventMetadata::new(format!($fmt), $level).with_target($target)
+        )
+    };
+    (@event $target:expr, $level:expr, $fmt:expr, $($args:tt)*) => {
+        $crate::event!(
+            @emit $target, $level, [],
+            $crate::EventMetadata::new(format!($fmt, $($args)*), $level).with_target($target)
+        )
+    };
+    // The metadata of the event is only created if the callsite is enabled. Since
+    // `STATIC_MAX_LEVEL` is a constant, statically disabled events are removed
+    // by the compiler.
+    (@emit $target:expr, $level:expr, [$($key:ident),*], $metadata:expr) => {{
+        static __CALLSITE: $crate::Callsite = $crate::Callsite::event($target, $level, &[$(stringify!($key)),*]);
+
+        if $crate::STATIC_MAX_LEVEL.enables($level) && !__CALLSITE.interest().is_never() {
+            $crate::with_subscriber(|s| {
+                s.event($metadata.with_callsite(&__CALLSITE));
+            });
+        }
+    }};
+    (target: $target:expr, level: $level:expr, $($args:tt)*) => {
+        $crate::event!(@event $target, $level, $($args)*)
     };
-    (level: $level:expr, $fmt:expr) => {
-        $crate::with_subscriber(|s| {
-            s.event($crate::EventMetadata::new(format!($fmt), $level));
-        });
+    (level: $level:expr, target: $target:expr, $($args:tt)*) => {
+        $crate::event!(@event $target, $level, $($args)*)
     };
-    (level: $level:expr, $fmt:expr, $($args:tt)*) => {
-        $crate::with_subscriber(|s| {
-            s.event($crate::EventMetadata::new(format!($fmt, $($args)*), $level));
-        });
+    (level: $level:expr, $($args:tt)*) => {
+        $crate::event!(@event module_path!(), $level, $($args)*)
+    };
+}
+
+/// Converts the value of a field into a [`crate::Value`], keeping the type of
+/// primitive values and formatting all other values using `Display`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_value {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaDisplay as _, ViaToValue as _};
+
+        (&$crate::__private::DisplayValue(&$value)).__value()
+    }};
+}
+
+/// Creates a new span, which can be entered using the returned [`crate::Span`].
+///
+/// The span macro is invoked with a [`crate::Level`] and the name of the span,
+/// followed by zero-or-more fields, such as
+/// `span!(Level::Debug, "parse", file = path.display())`.
+///
+/// The target of the span, which is matched by filters, defaults to the path
+/// of the module which the macro was invoked from. To override it, add the
+/// `target` argument before the level, such as
+/// `span!(target: "db", Level::Debug, "query")`. The level, name and target
+/// must all be constant expressions.
+///
+/// If the span is disabled, either statically or by the filter, a disabled
+/// span is returned, which never emits anything.
+#[macro_export]
+macro_rules! span {
+    (target: $target:expr, $level:expr, $name:expr $(, $key:ident = $value:expr )* $(,)?) => {{
+        static __CALLSITE: $crate::Callsite = $crate::Callsite::span($target, $level, &[$(stringify!($key)),*]);
+
+        if $crate::STATIC_MAX_LEVEL.enables($level) && !__CALLSITE.interest().is_never() {
+            $crate::with_subscriber(|s| {
+                s.new_span(
+                    $crate::SpanMetadata::new($name, $level)
+                        .with_target($target)
+                        .with_callsite(&__CALLSITE)
+                        $(
+                            .with_field(stringify!($key), $crate::__field_value!($value))
+                        )*
+                )
+            })
+        } else {
+            $crate::Span::none()
+        }
+    }};
+    ($level:expr, $name:expr $(, $key:ident = $value:expr )* $(,)?) => {
+        $crate::span!(target: module_path!(), $level, $name $(, $key = $value )*)
     };
 }
 
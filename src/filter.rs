@@ -1,4 +1,7 @@
-use crate::{EventMetadata, FieldSet, Level, SpanMetadata};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use crate::{Callsite, CallsiteKind, EventMetadata, FieldSet, Interest, Level, LevelFilter, SpanMetadata, Value};
 
 /// A filter for filtering out unwanted spans and events, based on a set of
 /// directives.
@@ -20,34 +23,109 @@ use crate::{EventMetadata, FieldSet, Level, SpanMetadata};
 /// target[field=value]=level
 /// ```
 ///
-/// - `target` matches the name of the function or method which the span or
-///   event was emitted from. `target` only matches the first part of the target
-///   name - of the `target` filter is set to `backend`, spans and events from
-///   any nested functions, such as `backend::api` and `backend::db` are also
-///   matched.
+/// - `target` matches the target of the span or event, which is the name of
+///   the function or method which it was emitted from, unless overridden.
+///   `target` only matches the first part of the target name - if the `target`
+///   filter is set to `backend`, spans and events from any nested functions,
+///   such as `backend::api` and `backend::db` are also matched. Targets are
+///   matched by their `::`-separated segments, so `backend` does not match
+///   `backend_legacy::api`.
+///
+///   Events are matched both by their own target, which is the module they
+///   were emitted from unless overridden, and by the targets of all spans they
+///   were emitted within. Given the filter `backend::db=debug`, events emitted
+///   from the `backend::db` module are emitted, as are events emitted from
+///   anywhere beneath a `backend::db` span - including nested spans of other
+///   modules.
+///
+///   Targets may contain wildcards, in place of entire segments:
+///     - `*` matches any single segment, such that `app::*::handler` matches
+///       both `app::users::handler` and `app::orders::handler`.
+///     - `**` matches any number of segments, including none, such that
+///       `**::parse` matches any function named `parse` in any module.
 ///
 /// - `field` is used to match fields within a span or event. Each field has a
 ///   corresponding "mode" and "value". Modes define how the field value should
-///   be checked - currently there are 4 modes:
+///   be checked - currently there are 10 modes:
 ///     - `=`: field value **must equal** with the given filter value
 ///     - `~=`: field value **must contain** with the given filter value
 ///     - `^=`: field value **must start** with the given filter value
 ///     - `$=`: field value **must end** with the given filter value
+///     - `*=`: field value **must match** the given glob pattern, where `*`
+///       matches any number of characters and `?` matches a single character
+///     - `!=`: field value **must not equal** the given filter value
+///     - `>`, `>=`, `<`, `<=`: field value **must be greater than**, **greater
+///       than or equal to**, **less than** or **less than or equal to** the
+///       given filter value
+///
+///   If both the field value and the filter value are numbers, `=`, `!=` and
+///   the ordering modes compare them numerically, so `[status>=500]` matches a
+///   `status` of `503`, but not `60`, and `[ratio=1]` matches a `ratio` of
+///   `1.0`. Otherwise, `=` and `!=` compare them as text, while the ordering
+///   modes never match non-numeric values.
 ///
 ///   Following the field mode, `value`s match the value of the field itself,
-///   depending on the mode. For example:
+///   depending on the mode. Values may be quoted, which is required if they
+///   contain characters other than letters, digits, `_`, `-`, `.`, `:`, `*` or
+///   `?`. For example:
 ///     - `[name~="John"]`: matches all items which have a field, `name`, which
 ///       contains the value `John`.
 ///     - `[description^="Fantastic"]`: matches all items which have a field,
 ///       `description`, which start with the value `Fantastic`.
+///     - `[path*="/api/*/orders"]`: matches all items which have a field,
+///       `path`, such as `/api/v2/orders`.
+///
+///   With the `regex` feature enabled, which it is by default, the `=` mode
+///   also accepts a regular expression, delimited by slashes. The expression
+///   must match some part of the field value, unless it is anchored:
+///     - `[id=/^(12|13|99)$/]`: matches all items which have a field, `id`,
+///       which is either `12`, `13` or `99`.
+///
+///   All patterns and regular expressions are compiled once, when the filter is
+///   parsed.
+///
+///   When an event is matched by one of its ancestor spans, the fields may be
+///   on either the event or that span. Given the filter
+///   `handle_request[user=alice]=trace`, all events beneath a `handle_request`
+///   span with a `user` field of `alice` are emitted.
 ///
 /// - `level` defines the maximum level of the directive. If any span or event
 ///   matches the directive, it must also have a verbosity level which is equal
-///   or less than this level.
+///   or less than this level. The level may also be `off`, which excludes all
+///   matching spans and events.
+///
+/// # Exclusions
+///
+/// Directives which are prefixed with `-`, such as `-backend::db`, exclude all
+/// matching spans and events - the same as `backend::db=off`. Exclusions cannot
+/// define a level, but they can still match fields, such that
+/// `-backend[user=healthcheck]` only excludes items from `backend` which have a
+/// `user` field of `healthcheck`.
+///
+/// Given the filter `backend=trace,-backend::db`, everything from `backend` is
+/// emitted, except for `backend::db`.
+///
+/// # Precedence
+///
+/// When multiple directives match the same span or event, only the most
+/// specific directive decides whether it is emitted:
+///
+/// 1. the directive with the longest matching `target` wins, such that
+///    `backend=error,backend::api=trace` emits everything from `backend::api`,
+///    while `backend=trace,backend::api=error` only emits errors from it.
+///    Targets are compared by their number of named segments, then by their
+///    number of `*` wildcards;
+/// 2. if the targets are equally long, the directive with the most field
+///    filters wins;
+/// 3. if both are equal, exclusions win over other directives;
+/// 4. otherwise, the directive which was defined last wins.
+///
+/// Directives without a `target` or field filters define the default level,
+/// which is only used when no other directive matches.
 #[derive(Debug)]
 pub struct EnvFilter {
     directives: Vec<Directive>,
-    default_level: Option<Level>,
+    default_level: Option<LevelFilter>,
 }
 
 impl EnvFilter {
@@ -63,6 +141,25 @@ impl EnvFilter {
             default_level,
         }
     }
+
+    /// Combines the directives of both filters into a single filter, the same
+    /// as if they were parsed from a single, comma-separated filter.
+    pub(crate) fn merge(self, other: EnvFilter) -> Self {
+        let directives = [self, other]
+            .into_iter()
+            .flat_map(|filter| {
+                let default = filter.default_level.map(|level| Directive {
+                    module: None,
+                    fields: Vec::new(),
+                    level,
+                });
+
+                default.into_iter().chain(filter.directives)
+            })
+            .collect();
+
+        Self::from_directives(directives)
+    }
 }
 
 impl Default for EnvFilter {
@@ -70,16 +167,16 @@ impl Default for EnvFilter {
         Self::from_directives(vec![Directive {
             module: None,
             fields: Vec::new(),
-            level: Level::Error,
+            level: LevelFilter::Error,
         }])
     }
 }
 
 #[derive(Debug)]
 struct Directive {
-    pub module: Option<String>,
+    pub module: Option<Target>,
     pub fields: Vec<FieldFilter>,
-    pub level: Level,
+    pub level: LevelFilter,
 }
 
 #[derive(Debug)]
@@ -87,14 +184,220 @@ struct FieldFilter {
     pub key: String,
     pub value: String,
     pub mode: FilterMode,
+
+    /// The value of the filter, if it is a number.
+    pub number: Option<Number>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 enum FilterMode {
     Equal,
+    NotEqual,
     Contains,
     StartsWith,
     EndsWith,
+    GreaterThan,
+    GreaterOrEqual,
+    LessThan,
+    LessOrEqual,
+    Glob(Glob),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+/// A number, parsed from either a field value or the value of a field filter.
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn parse(value: &str) -> Option<Self> {
+        if let Ok(value) = value.parse::<i128>() {
+            return Some(Number::Int(value));
+        }
+
+        value.parse::<f64>().ok().filter(|v| !v.is_nan()).map(Number::Float)
+    }
+
+    fn of_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::I64(value) => Some(Number::Int(i128::from(*value))),
+            Value::U64(value) => Some(Number::Int(i128::from(*value))),
+            Value::F64(value) if !value.is_nan() => Some(Number::Float(*value)),
+            Value::Str(value) | Value::Debug(value) | Value::Display(value) => Number::parse(value),
+            _ => None,
+        }
+    }
+
+    /// Compares two numbers, only comparing them as floats if either of them
+    /// is a float.
+    fn compare(self, other: Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(lhs), Number::Int(rhs)) => Some(lhs.cmp(&rhs)),
+            (lhs, rhs) => lhs.as_f64().partial_cmp(&rhs.as_f64()),
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(value) => value as f64,
+            Number::Float(value) => value,
+        }
+    }
+}
+
+/// The target of a directive, split into `::`-separated segments.
+#[derive(Debug, Clone)]
+struct Target {
+    segments: Vec<TargetSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TargetSegment {
+    /// Matches a segment with the exact name.
+    Name(String),
+
+    /// Matches any single segment, written as `*`.
+    Any,
+
+    /// Matches zero or more segments, written as `**`.
+    AnyDepth,
+}
+
+impl Target {
+    /// Determines whether the target matches the given name, or any of its
+    /// parents, such that `backend` matches `backend::api`, but not
+    /// `backend_legacy`.
+    fn matches(&self, name: &str) -> bool {
+        fn matches_segments(segments: &[TargetSegment], names: &[&str]) -> bool {
+            match segments.split_first() {
+                None => true,
+                Some((TargetSegment::AnyDepth, rest)) => {
+                    (0..=names.len()).any(|idx| matches_segments(rest, &names[idx..]))
+                }
+                Some((segment, rest)) => names.split_first().is_some_and(|(name, names)| {
+                    let matched = match segment {
+                        TargetSegment::Name(expected) => name == expected,
+                        _ => true,
+                    };
+
+                    matched && matches_segments(rest, names)
+                }),
+            }
+        }
+
+        let names: Vec<&str> = name.split("::").collect();
+
+        matches_segments(&self.segments, &names)
+    }
+
+    /// Gets the number of named segments and single-segment wildcards in the
+    /// target, used to determine which directive is the most specific.
+    fn specificity(&self) -> (usize, usize) {
+        let named = self
+            .segments
+            .iter()
+            .filter(|segment| matches!(segment, TargetSegment::Name(_)))
+            .count();
+
+        let wildcards = self
+            .segments
+            .iter()
+            .filter(|segment| **segment == TargetSegment::Any)
+            .count();
+
+        (named, wildcards)
+    }
+}
+
+/// A compiled glob pattern, used by the `*=` field filter mode.
+#[derive(Debug, Clone)]
+struct Glob {
+    tokens: Vec<GlobToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobToken {
+    /// Matches the exact text.
+    Literal(String),
+
+    /// Matches any single character, written as `?`.
+    Any,
+
+    /// Matches zero or more characters, written as `*`.
+    Star,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Self {
+        let mut tokens = Vec::new();
+
+        for c in pattern.chars() {
+            match (c, tokens.last_mut()) {
+                // Consecutive stars are equivalent to a single star.
+                ('*', Some(GlobToken::Star)) => {}
+                ('*', _) => tokens.push(GlobToken::Star),
+                ('?', _) => tokens.push(GlobToken::Any),
+                (c, Some(GlobToken::Literal(literal))) => literal.push(c),
+                (c, _) => tokens.push(GlobToken::Literal(c.to_string())),
+            }
+        }
+
+        Self { tokens }
+    }
+
+    /// Checks whether the glob matches the entire value.
+    ///
+    /// Tokens are matched greedily from left to right. When a token doesn't
+    /// match, only the last star is backtracked to, which then consumes one
+    /// more character. Earlier stars never have to be revisited, since any
+    /// match they could give up is also reachable from the last star, so
+    /// matching takes at most quadratic time.
+    fn matches(&self, value: &str) -> bool {
+        let mut token = 0;
+        let mut rest = value;
+
+        // Index of the token after the last star, and the value it matched.
+        let mut backtrack: Option<(usize, &str)> = None;
+
+        loop {
+            let matched = match self.tokens.get(token) {
+                Some(GlobToken::Star) => {
+                    token += 1;
+                    backtrack = Some((token, rest));
+                    continue;
+                }
+                Some(GlobToken::Literal(literal)) => rest.strip_prefix(literal.as_str()),
+                Some(GlobToken::Any) => {
+                    let mut chars = rest.chars();
+                    chars.next().map(|_| chars.as_str())
+                }
+                None if rest.is_empty() => return true,
+                None => None,
+            };
+
+            if let Some(matched) = matched {
+                token += 1;
+                rest = matched;
+                continue;
+            }
+
+            let Some((star_token, star_rest)) = backtrack else {
+                return false;
+            };
+
+            let mut chars = star_rest.chars();
+            if chars.next().is_none() {
+                return false;
+            }
+
+            token = star_token;
+            rest = chars.as_str();
+            backtrack = Some((token, rest));
+        }
+    }
 }
 
 /// Defines the default environment variable to use in [`from_default_env`].
@@ -129,13 +432,162 @@ pub fn from_env(env_name: &str) -> Result<EnvFilter, ParseError> {
     parse(env_value.to_string_lossy())
 }
 
+/// An error raised while parsing an [`EnvFilter`], pointing to the malformed
+/// part of the filter.
+///
+/// The [`Display`] implementation gives a single-line description of the
+/// error. For command-line tools, [`ParseError::render`] can be used to point
+/// out the error within the directive:
+/// ```
+/// let err = libftrace::filter::parse("backend=info,db=verbose").unwrap_err();
+///
+/// assert_eq!(err.render(), "unknown level `verbose`, expected one of `trace`, \
+///     `debug`, `info`, `warn`, `error` or `off`\n  db=verbose\n     ^^^^^^^");
+/// ```
+///
+/// [`Display`]: std::fmt::Display
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The kind of error which was raised.
+    pub kind: ParseErrorKind,
+
+    /// The byte offset of the error within the filter.
+    pub offset: usize,
+
+    /// The length of the malformed text, in bytes. If the error was caused by
+    /// missing text, the length is zero.
+    pub len: usize,
+
+    /// The text of the directive which contains the error.
+    pub directive: String,
+
+    /// The byte offset of the directive within the filter.
+    pub directive_offset: usize,
+}
+
+impl ParseError {
+    /// Renders the error as a human-readable explanation, followed by the
+    /// malformed directive with the error pointed out by carets:
+    /// ```text
+    /// unknown level `verbose`, expected one of `trace`, `debug`, `info`, `warn`, `error` or `off`
+    ///   db=verbose
+    ///      ^^^^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let offset = self.offset.saturating_sub(self.directive_offset);
+
+        let column = self
+            .directive
+            .get(..offset)
+            .map_or_else(|| self.directive.chars().count(), |text| text.chars().count());
+
+        let width = self
+            .directive
+            .get(offset..)
+            .and_then(|rest| rest.get(..self.len))
+            .map_or(1, |text| text.chars().count().max(1));
+
+        format!(
+            "{}\n  {}\n  {:column$}{}",
+            self.kind,
+            self.directive,
+            "",
+            "^".repeat(width)
+        )
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid filter directive `{}` at offset {}: {}",
+            self.directive, self.offset, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Defines the different kinds of [`ParseError`]s.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ParseError {
+pub enum ParseErrorKind {
+    /// A directive was empty, such as between two consecutive commas.
+    EmptyDirective,
+
     /// A directive was defined without having any level.
     MissingLevel,
 
     /// A level was given to a directive, but was invalid or malformed.
     InvalidLevel(String),
+
+    /// A level was given to an exclusion directive, which cannot have one.
+    UnexpectedLevel,
+
+    /// A segment of a directive target was empty, or was neither a name nor a
+    /// wildcard.
+    InvalidTargetSegment(String),
+
+    /// A field filter was defined without a field name.
+    MissingFieldKey,
+
+    /// A field filter had no mode, or the mode was invalid.
+    InvalidFieldMode,
+
+    /// A field filter was defined without a value to match against.
+    MissingFieldValue,
+
+    /// A quoted field value was never closed.
+    UnterminatedString,
+
+    /// A regular expression was never closed.
+    UnterminatedRegex,
+
+    /// A regular expression was given to a field mode other than `=`.
+    UnexpectedRegex,
+
+    /// A regular expression could not be compiled.
+    InvalidRegex(String),
+
+    /// A list of field filters was never closed.
+    UnclosedFieldList,
+
+    /// An unexpected character was found, either within or after a directive.
+    UnexpectedCharacter(char),
+}
+
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::EmptyDirective => write!(f, "expected a directive"),
+            ParseErrorKind::MissingLevel => write!(f, "expected a level after `=`"),
+            ParseErrorKind::InvalidLevel(level) => write!(
+                f,
+                "unknown level `{level}`, expected one of `trace`, `debug`, `info`, `warn`, `error` or `off`"
+            ),
+            ParseErrorKind::UnexpectedLevel => write!(f, "exclusion directives cannot have a level"),
+            ParseErrorKind::InvalidTargetSegment(segment) if segment.is_empty() => {
+                write!(f, "expected a target segment")
+            }
+            ParseErrorKind::InvalidTargetSegment(segment) => {
+                write!(f, "invalid target segment `{segment}`, expected a name, `*` or `**`")
+            }
+            ParseErrorKind::MissingFieldKey => write!(f, "expected a field name"),
+            ParseErrorKind::InvalidFieldMode => {
+                write!(
+                    f,
+                    "expected one of `=`, `!=`, `>`, `>=`, `<`, `<=`, `~=`, `^=`, `$=` or `*=` after field name"
+                )
+            }
+            ParseErrorKind::MissingFieldValue => write!(f, "expected a value to match the field against"),
+            ParseErrorKind::UnterminatedString => write!(f, "unterminated string, expected closing `\"`"),
+            ParseErrorKind::UnterminatedRegex => write!(f, "unterminated regular expression, expected closing `/`"),
+            ParseErrorKind::UnexpectedRegex => write!(f, "regular expressions can only be matched using `=`"),
+            ParseErrorKind::InvalidRegex(err) => write!(f, "invalid regular expression: {err}"),
+            ParseErrorKind::UnclosedFieldList => write!(f, "expected `,` or `]` after field filter"),
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character `{c}`"),
+        }
+    }
 }
 
 /// Parses the given value into an [`EnvFilter`], returning any raised errors to
@@ -145,13 +597,18 @@ pub fn parse<V: AsRef<str>>(from: V) -> Result<EnvFilter, ParseError> {
     let mut parser = Parser {
         slice: from.as_ref(),
         idx: 0,
+        directive_start: 0,
     };
 
     while !parser.eof() {
         directives.push(parser.parse_directive()?);
 
-        // Unless there's a delimiting comma, stop parsing.
+        // Unless there's a delimiting comma, the filter must end here.
         if !parser.check(',') {
+            if let Some(c) = parser.peek() {
+                return Err(parser.error(ParseErrorKind::UnexpectedCharacter(c), parser.idx, c.len_utf8()));
+            }
+
             break;
         }
     }
@@ -161,24 +618,40 @@ pub fn parse<V: AsRef<str>>(from: V) -> Result<EnvFilter, ParseError> {
 
 struct Parser<'src> {
     slice: &'src str,
+
+    /// The current byte offset into `slice`.
     idx: usize,
+
+    /// The byte offset of the directive which is currently being parsed.
+    directive_start: usize,
 }
 
 impl<'src> Parser<'src> {
     #[inline]
     pub fn eof(&self) -> bool {
-        self.idx >= self.slice.len() - 1
+        self.idx >= self.slice.len()
     }
 
     #[inline]
     fn peek(&self) -> Option<char> {
-        self.slice.chars().nth(self.idx)
+        self.slice[self.idx..].chars().next()
     }
 
     #[inline]
     fn check(&mut self, c: char) -> bool {
         if self.peek() == Some(c) {
-            self.idx += 1;
+            self.idx += c.len_utf8();
+            return true;
+        }
+
+        false
+    }
+
+    /// Consumes the given text, if the remaining input starts with it.
+    #[inline]
+    fn check_str(&mut self, text: &str) -> bool {
+        if self.slice[self.idx..].starts_with(text) {
+            self.idx += text.len();
             return true;
         }
 
@@ -188,13 +661,13 @@ impl<'src> Parser<'src> {
     #[inline]
     fn take_while<F: FnMut(char) -> bool>(&mut self, mut f: F) -> Option<&'src str> {
         let start = self.idx;
-        let mut ci = self.slice[self.idx..].chars().peekable();
 
-        while let Some(c) = ci.peek()
-            && f(*c)
-        {
-            self.idx += 1;
-            ci.next();
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+
+            self.idx += c.len_utf8();
         }
 
         if start == self.idx {
@@ -209,61 +682,184 @@ impl<'src> Parser<'src> {
         self.take_while(|c| c.is_ascii_alphabetic())
     }
 
+    /// Parses the key of a field filter, which may contain letters, digits and
+    /// underscores, but may not start with a digit.
+    #[inline]
+    fn field_key(&mut self) -> Option<&'src str> {
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
     #[inline]
     fn module_name(&mut self) -> Option<&'src str> {
-        self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '-' | '_'))
+        self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '-' | '_' | '*'))
     }
 
     #[inline]
-    fn value(&mut self) -> Option<&'src str> {
-        if self.peek() == Some('"') {
-            let start = self.idx;
-            let mut ci = self.slice[self.idx..].char_indices().peekable();
+    fn value(&mut self) -> Result<Option<&'src str>, ParseError> {
+        let start = self.idx;
+
+        if self.check('"') {
+            let value = self.take_while(|c| c != '"').unwrap_or_default();
+
+            if !self.check('"') {
+                return Err(self.error(ParseErrorKind::UnterminatedString, start, self.idx - start));
+            }
+
+            return Ok(Some(value));
+        }
+
+        Ok(self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '*' | '?')))
+    }
+
+    /// Parses a regular expression, delimited by slashes. Slashes within the
+    /// expression can be escaped using `\/`.
+    fn regex(&mut self) -> Result<String, ParseError> {
+        let start = self.idx;
+        self.check('/');
+
+        let mut pattern = String::new();
+        let mut escaped = false;
+
+        while let Some(c) = self.peek() {
+            self.idx += c.len_utf8();
+
+            match c {
+                '/' if escaped => pattern.push('/'),
+                '/' => return Ok(pattern),
+                '\\' if !escaped => {
+                    escaped = true;
+                    continue;
+                }
+                c if escaped => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                c => pattern.push(c),
+            }
+
+            escaped = false;
+        }
+
+        Err(self.error(ParseErrorKind::UnterminatedRegex, start, self.idx - start))
+    }
+
+    /// Gets the text of the directive which is currently being parsed, which
+    /// extends until the next comma outside of any field list.
+    fn directive_text(&self) -> &'src str {
+        let rest = &self.slice[self.directive_start..];
+        let (mut in_fields, mut in_string, mut in_regex, mut escaped) = (false, false, false, false);
+
+        for (idx, c) in rest.char_indices() {
+            // Regular expressions may contain any character, including
+            // brackets and commas, except for unescaped slashes.
+            if in_regex {
+                match c {
+                    '/' if !escaped => in_regex = false,
+                    '\\' if !escaped => {
+                        escaped = true;
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                escaped = false;
+                continue;
+            }
 
-            match ci.next() {
-                Some((offset, '"')) => return Some(&self.slice[start..start + offset]),
-                None => return None,
+            match c {
+                '"' if in_fields => in_string = !in_string,
+                '/' if in_fields && !in_string => in_regex = true,
+                '[' if !in_string => in_fields = true,
+                ']' if !in_string => in_fields = false,
+                ',' if !in_fields && !in_string => return &rest[..idx],
                 _ => {}
             }
         }
 
-        self.take_while(|c| c.is_ascii_alphanumeric())
+        rest
+    }
+
+    /// Creates a new [`ParseError`] at the given byte offset, within the
+    /// directive which is currently being parsed.
+    fn error(&self, kind: ParseErrorKind, offset: usize, len: usize) -> ParseError {
+        let directive = self.directive_text();
+
+        ParseError {
+            kind,
+            offset,
+            len: len.min((self.directive_start + directive.len()).saturating_sub(offset)),
+            directive: directive.to_string(),
+            directive_offset: self.directive_start,
+        }
     }
 
     pub fn parse_directive(&mut self) -> Result<Directive, ParseError> {
+        self.directive_start = self.idx;
+
         let mut directive = Directive {
             module: None,
             fields: Vec::new(),
-            level: Level::Info,
+            level: LevelFilter::Info,
         };
 
-        if let Some(module_str) = self.module_name() {
-            directive.module = Some(module_str.to_string());
-        }
+        // Exclusions are prefixed with `-` and never have a level.
+        let excluded = self.check('-');
+
+        let module_start = self.idx;
+        let mut module = self.module_name();
 
         // Parse zero-or-more field filters in the directive.
         if self.check('[') {
             while !self.check(']') {
                 directive.fields.push(self.parse_filter()?);
 
-                // Unless there's a delimiting comma, stop parsing.
+                // Unless there's a delimiting comma, the list must be closed.
                 if !self.check(',') {
+                    if !self.check(']') {
+                        return Err(self.error(ParseErrorKind::UnclosedFieldList, self.idx, 1));
+                    }
+
                     break;
                 }
             }
+        }
 
-            self.check(']');
+        // Any other character after the target can't be part of the directive,
+        // such as a character which isn't allowed in names.
+        match self.peek() {
+            None | Some('=' | ',') => {}
+            Some(c) => return Err(self.error(ParseErrorKind::UnexpectedCharacter(c), self.idx, c.len_utf8())),
         }
 
+        if excluded {
+            if self.peek() == Some('=') {
+                return Err(self.error(ParseErrorKind::UnexpectedLevel, self.idx, 1));
+            }
+
+            if module.is_none() && directive.fields.is_empty() {
+                return Err(self.error(ParseErrorKind::EmptyDirective, self.idx, 0));
+            }
+
+            directive.level = LevelFilter::Off;
+        }
         // Parse the level required for any items to pass through it.
-        if self.check('=') {
+        else if self.check('=') {
+            let start = self.idx;
             let Some(level_str) = self.identifier() else {
-                return Err(ParseError::MissingLevel);
+                return Err(self.error(ParseErrorKind::MissingLevel, start, 0));
             };
 
-            directive.level = match Level::try_from(level_str) {
+            directive.level = match LevelFilter::try_from(level_str) {
                 Ok(level) => level,
-                Err(_) => return Err(ParseError::InvalidLevel(level_str.to_string())),
+                Err(_) => {
+                    let kind = ParseErrorKind::InvalidLevel(level_str.to_string());
+
+                    return Err(self.error(kind, start, level_str.len()));
+                }
             };
         }
         // If we don't see an assignment for the level, we assume that the entire directive
@@ -271,100 +867,238 @@ impl<'src> Parser<'src> {
         // ```
         // RUST_LOG=info
         // ```
-        else if let Some(module_str) = directive.module.take() {
-            directive.level = match Level::try_from(module_str.as_str()) {
+        else if let Some(module_str) = module.filter(|_| directive.fields.is_empty()) {
+            module = None;
+
+            directive.level = match LevelFilter::try_from(module_str) {
                 Ok(level) => level,
-                Err(_) => return Err(ParseError::InvalidLevel(module_str)),
+                Err(_) => {
+                    let kind = ParseErrorKind::InvalidLevel(module_str.to_string());
+
+                    return Err(self.error(kind, self.directive_start, module_str.len()));
+                }
             };
+        } else if module.is_none() && directive.fields.is_empty() {
+            return Err(self.error(ParseErrorKind::EmptyDirective, self.idx, 0));
+        }
+
+        if let Some(module_str) = module {
+            directive.module = Some(self.target(module_str, module_start)?);
         }
 
         Ok(directive)
     }
 
+    /// Parses the given target of a directive into a [`Target`], where
+    /// `start` is the offset of the target within the filter.
+    fn target(&self, target: &str, start: usize) -> Result<Target, ParseError> {
+        let mut segments = Vec::new();
+        let mut offset = start;
+
+        for segment in target.split("::") {
+            segments.push(match segment {
+                "*" => TargetSegment::Any,
+                "**" => TargetSegment::AnyDepth,
+                segment if segment.is_empty() || segment.contains([':', '*']) => {
+                    let kind = ParseErrorKind::InvalidTargetSegment(segment.to_string());
+
+                    return Err(self.error(kind, offset, segment.len()));
+                }
+                segment => TargetSegment::Name(segment.to_string()),
+            });
+
+            offset += segment.len() + 2;
+        }
+
+        Ok(Target { segments })
+    }
+
     fn parse_filter(&mut self) -> Result<FieldFilter, ParseError> {
         let mut filter = FieldFilter {
             key: String::new(),
             mode: FilterMode::Equal,
             value: String::new(),
+            number: None,
         };
 
         // Parse the name of the field filter.
-        let Some(key_str) = self.identifier() else { todo!() };
+        let Some(key_str) = self.field_key() else {
+            let len = self.peek().map_or(0, char::len_utf8);
+
+            return Err(self.error(ParseErrorKind::MissingFieldKey, self.idx, len));
+        };
         filter.key = key_str.to_string();
 
         // Parse the mode of the field filter.
+        let mode_start = self.idx;
         filter.mode = if self.check('=') {
             FilterMode::Equal
-        } else if self.check('~') && self.check('=') {
+        } else if self.check_str("!=") {
+            FilterMode::NotEqual
+        } else if self.check_str(">=") {
+            FilterMode::GreaterOrEqual
+        } else if self.check('>') {
+            FilterMode::GreaterThan
+        } else if self.check_str("<=") {
+            FilterMode::LessOrEqual
+        } else if self.check('<') {
+            FilterMode::LessThan
+        } else if self.check_str("~=") {
             FilterMode::Contains
-        } else if self.check('^') && self.check('=') {
+        } else if self.check_str("^=") {
             FilterMode::StartsWith
-        } else if self.check('$') && self.check('=') {
+        } else if self.check_str("$=") {
             FilterMode::EndsWith
+        } else if self.check_str("*=") {
+            FilterMode::Glob(Glob::new(""))
         } else {
-            todo!()
+            let len = self.slice[mode_start..].chars().next().map_or(0, char::len_utf8);
+
+            return Err(self.error(ParseErrorKind::InvalidFieldMode, mode_start, len));
         };
 
-        // Parse the matching value of the field filter.
-        filter.value = if let Some(value) = self.value() {
-            value.to_string()
-        } else {
-            todo!()
+        // Parse the matching value of the field filter, which may be a regular
+        // expression.
+        if self.peek() == Some('/') {
+            let start = self.idx;
+            let pattern = self.regex()?;
+
+            if !matches!(filter.mode, FilterMode::Equal) {
+                return Err(self.error(ParseErrorKind::UnexpectedRegex, start, self.idx - start));
+            }
+
+            filter.mode = self.compile_regex(&pattern, start)?;
+            filter.value = pattern;
+
+            return Ok(filter);
+        }
+
+        filter.value = match self.value()? {
+            Some(value) => value.to_string(),
+            None => return Err(self.error(ParseErrorKind::MissingFieldValue, self.idx, 0)),
         };
 
+        if let FilterMode::Glob(glob) = &mut filter.mode {
+            *glob = Glob::new(&filter.value);
+        }
+
+        filter.number = Number::parse(&filter.value);
+
         Ok(filter)
     }
+
+    #[cfg(feature = "regex")]
+    fn compile_regex(&self, pattern: &str, start: usize) -> Result<FilterMode, ParseError> {
+        match regex::Regex::new(pattern) {
+            Ok(regex) => Ok(FilterMode::Regex(regex)),
+            Err(err) => {
+                let kind = ParseErrorKind::InvalidRegex(err.to_string());
+
+                Err(self.error(kind, start, self.idx - start))
+            }
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn compile_regex(&self, _pattern: &str, start: usize) -> Result<FilterMode, ParseError> {
+        let kind = ParseErrorKind::InvalidRegex(String::from("the `regex` feature is not enabled"));
+
+        Err(self.error(kind, start, self.idx - start))
+    }
 }
 
 impl EnvFilter {
     /// Attempts to determine whether the given [`SpanMetadata`] should be
     /// emitted, given the current directives of the filter.
     pub fn span_enabled(&self, span: &SpanMetadata) -> bool {
-        let directives: Vec<&Directive> = self.directives_for_span(span).collect();
+        self.enabled(span.level, self.directives_for_span(span))
+    }
 
-        if directives.is_empty() {
-            if let Some(default_level) = self.default_level {
-                return default_level <= span.level;
-            }
+    /// Attempts to determine whether the given [`EventMetadata`] should be
+    /// emitted, given the current directives of the filter.
+    ///
+    /// `ancestors` are all spans which the event was emitted within, from the
+    /// innermost to the outermost span.
+    pub fn event_enabled<'a, I>(&self, event: &EventMetadata, ancestors: I) -> bool
+    where
+        I: IntoIterator<Item = &'a SpanMetadata>,
+        I::IntoIter: Clone,
+    {
+        let ancestors = ancestors.into_iter();
+        let directives = self
+            .directives
+            .iter()
+            .filter(|dir| dir.handles_event(event, ancestors.clone()));
 
-            // If there's no applicable directives and no default level,
-            // the span should not be emitted.
-            return false;
-        }
+        self.enabled(event.level, directives)
+    }
 
-        // If any of the directive filters are met, the span should be emitted.
-        for directive in directives {
-            if span.level >= directive.level {
-                return true;
+    /// Determines the interest of the filter in the given [`Callsite`], without
+    /// knowing the field values or ancestors of any spans or events it creates.
+    pub fn callsite_interest(&self, callsite: &Callsite) -> Interest {
+        // Directives which match the callsite regardless of the values of its
+        // fields, and directives which might match it.
+        let always = |dir: &Directive| dir.fields.is_empty() && dir.handles_target(callsite.target());
+        let sometimes = |dir: &Directive| match callsite.kind() {
+            CallsiteKind::Span => {
+                dir.handles_target(callsite.target())
+                    && dir
+                        .fields
+                        .iter()
+                        .all(|filter| callsite.fields().contains(&filter.key.as_str()))
             }
-        }
 
-        false
-    }
+            // Events may be matched by any of their ancestors, which aren't
+            // known until the event is emitted.
+            CallsiteKind::Event => true,
+        };
 
-    /// Attempts to determine whether the given [`EventMetadata`] should be
-    /// emitted, given the current directives of the filter.
-    pub fn event_enabled(&self, event: &EventMetadata, parent_span: Option<&SpanMetadata>) -> bool {
-        let directives: Vec<&Directive> = self.directives_for_event(event, parent_span).collect();
+        // The most specific directive which always matches decides, unless an
+        // even more specific directive also matches.
+        let precedence = |(idx, dir): &(usize, &Directive)| (dir.specificity(), *idx);
+        let decider = self
+            .directives
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| always(dir))
+            .max_by_key(precedence);
 
-        if directives.is_empty() {
-            if let Some(default_level) = self.default_level {
-                return default_level <= event.level;
-            }
+        let enabled = match decider {
+            Some((_, dir)) => dir.level.enables(callsite.level()),
+            None => self
+                .default_level
+                .is_some_and(|default_level| default_level.enables(callsite.level())),
+        };
 
-            // If there's no applicable directives and no default level,
-            // the event should not be emitted.
-            return false;
-        }
+        let ambiguous = self
+            .directives
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| sometimes(dir))
+            .filter(|item| decider.is_none_or(|decider| precedence(item) > precedence(&decider)))
+            .any(|(_, dir)| dir.level.enables(callsite.level()) != enabled);
 
-        // If any of the directive filters are met, the event should be emitted.
-        for directive in directives {
-            if event.level >= directive.level {
-                return true;
-            }
+        match (ambiguous, enabled) {
+            (true, _) => Interest::Sometimes,
+            (false, true) => Interest::Always,
+            (false, false) => Interest::Never,
         }
+    }
 
-        false
+    /// Determines whether an item of the given level should be emitted, given
+    /// all the directives which handle it.
+    fn enabled<'a>(&self, level: Level, directives: impl Iterator<Item = &'a Directive>) -> bool {
+        // Only the most specific directive decides whether the item is emitted. If
+        // multiple directives are equally specific, the last one is picked.
+        match directives.max_by_key(|directive| directive.specificity()) {
+            Some(directive) => directive.level.enables(level),
+
+            // If there's no applicable directives and no default level,
+            // the item should not be emitted.
+            None => self
+                .default_level
+                .is_some_and(|default_level| default_level.enables(level)),
+        }
     }
 
     /// Returns an iterator of all the directives which would handle the given
@@ -372,66 +1106,92 @@ impl EnvFilter {
     fn directives_for_span(&self, span: &SpanMetadata) -> impl Iterator<Item = &Directive> {
         self.directives.iter().filter(|dir| dir.handles_span(span))
     }
+}
 
-    /// Returns an iterator of all the directives which would handle the given
-    /// [`EventMetadata`].
-    fn directives_for_event(
-        &self,
-        event: &EventMetadata,
-        parent_span: Option<&SpanMetadata>,
-    ) -> impl Iterator<Item = &Directive> {
-        self.directives.iter().filter(move |dir| {
-            parent_span.is_some_and(|span| dir.handles_span(span)) && dir.handles_field_set(&event.fields)
-        })
+impl FieldFilter {
+    /// Determines whether the given field value matches the filter.
+    fn matches(&self, field: &Value) -> bool {
+        // Comparisons are made numerically, if both values are numbers.
+        let ordering = || Number::of_value(field)?.compare(self.number?);
+
+        match &self.mode {
+            FilterMode::Equal => {
+                if let Some(ordering) = ordering() {
+                    return ordering == Ordering::Equal;
+                }
+            }
+            FilterMode::NotEqual => {
+                if let Some(ordering) = ordering() {
+                    return ordering != Ordering::Equal;
+                }
+            }
+            FilterMode::GreaterThan => return ordering().is_some_and(Ordering::is_gt),
+            FilterMode::GreaterOrEqual => return ordering().is_some_and(Ordering::is_ge),
+            FilterMode::LessThan => return ordering().is_some_and(Ordering::is_lt),
+            FilterMode::LessOrEqual => return ordering().is_some_and(Ordering::is_le),
+            _ => {}
+        }
+
+        let field_value = match field.as_str() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(field.to_string()),
+        };
+
+        match &self.mode {
+            FilterMode::Equal => field_value == self.value,
+            FilterMode::NotEqual => field_value != self.value,
+            FilterMode::Contains => field_value.contains(&self.value),
+            FilterMode::StartsWith => field_value.starts_with(&self.value),
+            FilterMode::EndsWith => field_value.ends_with(&self.value),
+            FilterMode::Glob(glob) => glob.matches(&field_value),
+            #[cfg(feature = "regex")]
+            FilterMode::Regex(regex) => regex.is_match(&field_value),
+            FilterMode::GreaterThan | FilterMode::GreaterOrEqual | FilterMode::LessThan | FilterMode::LessOrEqual => {
+                false
+            }
+        }
     }
 }
 
 impl Directive {
+    /// Gets the specificity of the directive, where directives of higher
+    /// specificity take precedence over directives of lower specificity.
+    fn specificity(&self) -> ((usize, usize), usize, bool) {
+        let target = self.module.as_ref().map_or((0, 0), Target::specificity);
+
+        (target, self.fields.len(), self.level == LevelFilter::Off)
+    }
+
     /// Determines whether the current [`Directive`] would handle the given
     /// [`SpanMetadata`].
     fn handles_span(&self, span: &SpanMetadata) -> bool {
-        if self.module.as_ref().is_some_and(|m| !span.name.starts_with(m)) {
-            return false;
-        }
-
-        self.handles_field_set(&span.fields)
+        self.handles_target(span.target) && self.handles_fields(&[&span.fields])
     }
 
     /// Determines whether the current [`Directive`] would handle the given
-    /// [`FieldSet`].
-    fn handles_field_set(&self, field_set: &FieldSet) -> bool {
-        for filter in &self.fields {
-            let Some((_, field)) = field_set.inner.iter().find(|(k, _)| *k == filter.key) else {
-                return false;
-            };
-
-            let field_value = format!("{field}");
-            let field_value = field_value.trim_matches('"');
+    /// [`EventMetadata`], emitted within the given ancestor spans.
+    fn handles_event<'a>(&self, event: &EventMetadata, mut ancestors: impl Iterator<Item = &'a SpanMetadata>) -> bool {
+        // When matched by an ancestor, the fields of the ancestor are also
+        // matched, so the directive applies to everything within the span.
+        (self.handles_target(event.target) && self.handles_fields(&[&event.fields]))
+            || ancestors
+                .any(|span| self.handles_target(span.target) && self.handles_fields(&[&event.fields, &span.fields]))
+    }
 
-            match filter.mode {
-                FilterMode::Equal => {
-                    if field_value != filter.value {
-                        return false;
-                    }
-                }
-                FilterMode::Contains => {
-                    if !field_value.contains(&filter.value) {
-                        return false;
-                    }
-                }
-                FilterMode::StartsWith => {
-                    if !field_value.starts_with(&filter.value) {
-                        return false;
-                    }
-                }
-                FilterMode::EndsWith => {
-                    if !field_value.ends_with(&filter.value) {
-                        return false;
-                    }
-                }
-            }
-        }
+    /// Determines whether the target of the current [`Directive`] matches the
+    /// given target, which is always the case if it has no target.
+    fn handles_target(&self, target: &str) -> bool {
+        self.module.as_ref().is_none_or(|m| m.matches(target))
+    }
 
-        true
+    /// Determines whether all field filters of the current [`Directive`] are
+    /// matched by a field in any of the given [`FieldSet`]s.
+    fn handles_fields(&self, field_sets: &[&FieldSet]) -> bool {
+        self.fields.iter().all(|filter| {
+            field_sets
+                .iter()
+                .flat_map(|field_set| field_set.inner.iter())
+                .any(|(key, field)| *key == filter.key && filter.matches(field))
+        })
     }
 }
@@ -0,0 +1,207 @@
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::{Context, EventMetadata, Level, Sink, SpanMetadata, Value};
+
+/// A [`Sink`] which writes each span and event as a single JSON object on its
+/// own line, also known as [JSON Lines].
+///
+/// Every object contains the following keys:
+/// - `timestamp`: the time of the record, formatted as RFC 3339.
+/// - `kind`: either `enter`, `exit` or `event`.
+/// - `level`: the verbosity level of the span or event.
+/// - `name`: the name of the span, or `message`: the message of the event.
+/// - `target`: the target of the span or event.
+/// - `fields`: an object containing all fields of the span or event.
+/// - `file` and `line`: the location where the span or event was created.
+/// - `depth`: the number of spans which are entered on the current thread.
+/// - `parent`: the name of the innermost entered span, or `null`.
+/// - `thread_id`: the numeric ID of the current thread, which matches the
+///   [`Context::thread_id`] given to all sinks.
+/// - `thread_name`: the name of the current thread, which is only written if
+///   the thread has a name.
+///
+/// Exit records also contain the elapsed time of the span in nanoseconds,
+/// `elapsed_ns`, as well as the return value of the span, `return`, if any.
+///
+/// [JSON Lines]: https://jsonlines.org/
+pub struct JsonSink<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonSink<W> {
+    /// Creates a new [`JsonSink`] which writes to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    fn write_line(&self, record: &JsonRecord<'_>) -> std::io::Result<()> {
+        // Render the record into a buffer first, so the writer is only locked
+        // for a single write.
+        let mut buf = Vec::with_capacity(256);
+        record.write_to(&mut buf)?;
+        buf.push(b'\n');
+
+        let mut writer = self.writer.lock().unwrap_or_else(|err| err.into_inner());
+
+        writer.write_all(&buf)?;
+        writer.flush()
+    }
+}
+
+impl JsonSink<std::io::Stdout> {
+    /// Creates a new [`JsonSink`] which writes to `stdout`.
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl JsonSink<std::io::Stderr> {
+    /// Creates a new [`JsonSink`] which writes to `stderr`.
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+    }
+}
+
+impl<W: Write + Send> Sink for JsonSink<W> {
+    fn enter_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.write_line(&JsonRecord::span("enter", span, cx))
+    }
+
+    fn exit_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.write_line(&JsonRecord::span("exit", span, cx))
+    }
+
+    fn event(&self, event: &EventMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.write_line(&JsonRecord {
+            kind: "event",
+            level: event.level,
+            title: ("message", &event.message),
+            target: event.target,
+            fields: event.fields().collect(),
+            location: event.location,
+            cx,
+            exit: None,
+        })
+    }
+}
+
+/// A single line of JSON output, which can represent any record.
+struct JsonRecord<'a> {
+    kind: &'static str,
+    level: Level,
+    title: (&'static str, &'a str),
+    target: &'a str,
+    fields: Vec<(&'static str, &'a Value)>,
+    location: &'static std::panic::Location<'static>,
+    cx: &'a Context<'a>,
+    exit: Option<&'a SpanMetadata>,
+}
+
+impl<'a> JsonRecord<'a> {
+    fn span(kind: &'static str, span: &'a SpanMetadata, cx: &'a Context<'a>) -> Self {
+        Self {
+            kind,
+            level: span.level,
+            title: ("name", span.name),
+            target: span.target,
+            fields: span.fields().collect(),
+            location: span.location,
+            cx,
+            exit: (kind == "exit").then_some(span),
+        }
+    }
+
+    fn write_to(&self, f: &mut dyn Write) -> std::io::Result<()> {
+        let timestamp = time::UtcDateTime::now()
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(std::io::Error::other)?;
+
+        write!(f, "{{\"timestamp\":")?;
+        write_str(f, &timestamp)?;
+
+        write!(f, ",\"kind\":\"{}\",\"level\":\"{}\"", self.kind, self.level.as_str())?;
+
+        write!(f, ",\"{}\":", self.title.0)?;
+        write_str(f, self.title.1)?;
+
+        write!(f, ",\"target\":")?;
+        write_str(f, self.target)?;
+
+        write!(f, ",\"fields\":{{")?;
+        for (idx, (key, value)) in self.fields.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ",")?;
+            }
+
+            write_str(f, key)?;
+            write!(f, ":")?;
+            write_value(f, value)?;
+        }
+        write!(f, "}}")?;
+
+        write!(f, ",\"file\":")?;
+        write_str(f, self.location.file())?;
+        write!(f, ",\"line\":{}", self.location.line())?;
+
+        write!(f, ",\"depth\":{},\"parent\":", self.cx.depth)?;
+        match self.cx.parent {
+            Some(parent) => write_str(f, parent.name)?,
+            None => write!(f, "null")?,
+        }
+
+        write!(f, ",\"thread_id\":{}", self.cx.thread_id)?;
+        if let Some(name) = std::thread::current().name() {
+            write!(f, ",\"thread_name\":")?;
+            write_str(f, name)?;
+        }
+
+        if let Some(span) = self.exit {
+            write!(f, ",\"elapsed_ns\":{}", span.elapsed().as_nanos())?;
+
+            if let Some(value) = span.return_value() {
+                write!(f, ",\"return\":")?;
+                write_value(f, value)?;
+            }
+        }
+
+        write!(f, "}}")
+    }
+}
+
+/// Writes the given value as a JSON string, escaping it as needed.
+pub(crate) fn write_str(f: &mut dyn Write, value: &str) -> std::io::Result<()> {
+    write!(f, "\"")?;
+
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+/// Writes the given [`Value`] as JSON, keeping booleans and numbers as their
+/// native JSON types.
+pub(crate) fn write_value(f: &mut dyn Write, value: &Value) -> std::io::Result<()> {
+    match value {
+        Value::Bool(value) => write!(f, "{value}"),
+        Value::I64(value) => write!(f, "{value}"),
+        Value::U64(value) => write!(f, "{value}"),
+
+        // JSON has no representation of infinite or NaN numbers.
+        Value::F64(value) if value.is_finite() => write!(f, "{value}"),
+        Value::F64(_) => write!(f, "null"),
+
+        Value::Str(value) | Value::Debug(value) | Value::Display(value) | Value::Error(value) => write_str(f, value),
+    }
+}
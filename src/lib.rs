@@ -78,6 +78,96 @@
 //! }
 //! ```
 //!
+//! The attribute can also be added to an `impl` block, which traces every
+//! method within it. The spans are named after the `Self` type and the trait,
+//! such as `app::Parser::parse` or `<app::Token as Display>::fmt`, while their
+//! targets only include the `Self` type, such as `app::Token::fmt`. Methods can
+//! override individual arguments with their own `#[traced]` attribute, or opt
+//! out using `#[traced(skip)]`. Since arguments are recorded using `Debug`,
+//! arguments such as the `Formatter` of a `Display` implementation must be
+//! skipped:
+//! ```
+//! use std::fmt::{Display, Formatter};
+//!
+//! use libftrace::*;
+//!
+//! struct Parser;
+//!
+//! #[traced(level = Debug)]
+//! impl Parser {
+//!     pub fn parse(&self) {
+//!         // ...
+//!     }
+//!
+//!     #[traced(level = Trace)]
+//!     fn next_token(&self) {
+//!         // ...
+//!     }
+//!
+//!     #[traced(skip)]
+//!     fn is_empty(&self) -> bool {
+//!         true
+//!     }
+//! }
+//!
+//! struct Token;
+//!
+//! #[traced(skip(f))]
+//! impl Display for Token {
+//!     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+//!         write!(f, "token")
+//!     }
+//! }
+//! ```
+//!
+//! Spans have both a name, which is shown by sinks, and a target, which is
+//! matched by filters. Both default to the path of the function, but can be
+//! overridden using the `name` and `target` arguments:
+//! ```
+//! use libftrace::*;
+//!
+//! #[traced(name = "insert user", target = "db::users")]
+//! pub fn insert_user() {
+//!     // ...
+//! }
+//! ```
+//!
+//! To emit an event when a function fails, add the `err` argument. Failures
+//! are `Err` values of a `Result`, `Break` values of a `ControlFlow` and `None`
+//! values of an `Option`, which are reported as misses. Similarly, `ret` emits
+//! the returned value. Primitive values keep their type, while other values
+//! are formatted using their `Debug` implementation, unless `Display` is given.
+//! The events default to the [`Error`][`Level::Error`] level and the level of
+//! the span, respectively:
+//! ```
+//! use libftrace::*;
+//!
+//! #[traced(err(level = Warn, Display), ret(level = Trace))]
+//! pub fn parse_port(input: &str) -> Result<u16, std::num::ParseIntError> {
+//!     input.parse()
+//! }
+//!
+//! #[traced(err(level = Debug))]
+//! pub fn cache_get(key: u64) -> Option<String> {
+//!     None
+//! }
+//! ```
+//!
+//! To span only part of a function, such as a loop or a closure, create a
+//! span using the [`span!`] macro. The returned [`Span`] can be entered any
+//! number of times, either using [`Span::enter`] or [`Span::in_scope`]:
+//! ```
+//! use libftrace::*;
+//!
+//! let span = span!(Level::Debug, "flush", pending = 3);
+//!
+//! span.in_scope(|| {
+//!     debug!("flushing pending writes");
+//! });
+//! ```
+//!
+//! [`span!`]: crate::span!
+//!
 //! #### Events
 //!
 //! Events can be created using the [`event!`] macro. It allows for a very
@@ -90,6 +180,16 @@
 //!
 //! [`event!`]: crate::event!
 //!
+//! Events are targeted at the module they were emitted from, so they can be
+//! filtered by it, even outside of any span. The target can also be set
+//! explicitly:
+//! ```
+//! use libftrace::*;
+//!
+//! event!(level: Level::Info, target: "auth", "a new user logged in!");
+//! info!(target: "auth", "failed login attempt");
+//! ```
+//!
 //! For convinience, there are also macros for each log level:
 //! ```
 //! use libftrace::*;
@@ -108,10 +208,15 @@
 //! effectively function as a key-value map, mapping some string-based key to a
 //! value.
 //!
-//! To attach fields with the `#[traced]` attribute, add the `fields()`
+//! All arguments of a `#[traced]` function are attached to its span as fields,
+//! using their `Debug` implementation. Arguments which don't implement `Debug`,
+//! or which shouldn't be recorded, must be skipped using `skip(...)`, or
+//! `skip_all` to skip every argument. The `self` receiver is never recorded.
+//!
+//! To attach other fields with the `#[traced]` attribute, add the `fields()`
 //! argument:
 //! ```rs
-//! #[traced(level = Info, fields(method = req.method, host = req.host))]
+//! #[traced(level = Info, skip(req), fields(method = req.method, host = req.host))]
 //! fn handle_request(req: Request) {
 //!     // ..
 //! }
@@ -121,11 +226,48 @@
 //! ```rs
 //! info!("failed login attempt", username = creds.username);
 //! ```
+//!
+//! ### Sinks
+//!
+//! All spans and events which pass the filter are sent to one or more
+//! [`Sink`]s. By default, they are written to `stdout` as coloured text, using
+//! [`PrettySink`]. To change where they are written, use [`set_sink`] or
+//! [`add_sink`]:
+//! ```
+//! use libftrace::*;
+//!
+//! set_sink(PrettySink::stderr());
+//! ```
+//!
+//! Spans and events can also be written as JSON, one object per line, using
+//! [`JsonSink`]. To select the format without changing any code, set the
+//! `LIBFTRACE_FORMAT` environment variable to either `pretty` or `json`.
+//!
+//! To inspect a run as a timeline, use [`ChromeSink`], which writes a trace
+//! file which can be opened in `chrome://tracing` or the [Perfetto UI].
+//!
+//! [Perfetto UI]: https://ui.perfetto.dev/
+//!
+//! ### Compile-time filtering
+//!
+//! To remove verbose spans and events from the program entirely, enable one of
+//! the `max_level_*` features, such as `max_level_info`. To only remove them
+//! from release builds, enable one of the `release_max_level_*` features
+//! instead:
+//! ```toml
+//! [dependencies]
+//! libftrace = { version = "*", features = ["release_max_level_warn"] }
+//! ```
+//!
+//! See [`STATIC_MAX_LEVEL`] for details.
 
-use std::cell::UnsafeCell;
+use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::fmt::Display;
-use std::sync::OnceLock;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
 #[macro_use]
 #[path = "enabled/macros.rs"]
@@ -137,26 +279,141 @@ pub mod macros;
 #[cfg(not(feature = "enabled"))]
 pub mod macros;
 
+pub mod callsite;
+pub mod chrome;
 pub mod filter;
+pub mod json;
+pub mod reload;
 mod render;
+pub mod sink;
+pub mod span;
+pub mod value;
 
 pub use libftrace_macros::*;
 use owo_colors::{OwoColorize, Style, Styled};
 
+pub use crate::callsite::*;
+pub use crate::chrome::*;
 pub use crate::filter::*;
-use crate::render::{RenderContext, Renderable};
-
-#[derive(Default)]
+pub use crate::json::*;
+pub use crate::reload::*;
+pub use crate::sink::*;
+use crate::span::SpanInner;
+pub use crate::span::*;
+pub use crate::value::{ToValue, Value};
+
+/// The global trace subscriber, which receives all spans and events.
+///
+/// The subscriber itself only contains configuration which is shared between
+/// all threads, such as the active filter and [`Sink`]s. Each thread keeps
+/// track of its own stack of entered spans, so spans entered on one thread
+/// never affect the depth or parent span of another.
 pub struct Subscriber {
+    filter: RwLock<Option<EnvFilter>>,
+    sinks: RwLock<Arc<[Arc<dyn Sink>]>>,
+    error_handler: RwLock<Arc<ErrorHandler>>,
+    error_count: AtomicU64,
+}
+
+impl Default for Subscriber {
+    fn default() -> Self {
+        Self {
+            filter: RwLock::new(None),
+            sinks: RwLock::new(Arc::new([Format::from_env().unwrap_or_default().stdout_sink().into()])),
+            error_handler: RwLock::new(Arc::new(ErrorHandler::default())),
+            error_count: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Per-thread state of the subscriber, containing the stack of entered spans.
+///
+/// The front of the stack is the innermost span, which is the parent of any
+/// spans or events created on the thread.
+struct LocalState {
+    thread_id: u64,
+    current: VecDeque<Arc<SpanInner>>,
+}
+
+impl Default for LocalState {
+    fn default() -> Self {
+        static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+        Self {
+            thread_id: NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed),
+            current: VecDeque::new(),
+        }
+    }
+}
+
+impl LocalState {
+    /// Copies the state needed to create a [`Context`] for items emitted on the
+    /// current thread.
+    fn snapshot(&self) -> LocalSnapshot {
+        LocalSnapshot {
+            depth: self.current.len(),
+            parent: self.current.front().cloned(),
+            thread_id: self.thread_id,
+        }
+    }
+}
+
+/// Owned copy of the per-thread state, taken before dispatching to sinks.
+///
+/// Sinks may themselves enter spans, such as by calling a `#[traced]` function,
+/// so the stack of the current thread must not be borrowed while they run.
+struct LocalSnapshot {
     depth: usize,
-    filter: Option<EnvFilter>,
-    current: VecDeque<SpanMetadata>,
+    parent: Option<Arc<SpanInner>>,
+    thread_id: u64,
 }
 
-unsafe impl Send for Subscriber {}
-unsafe impl Sync for Subscriber {}
+impl LocalSnapshot {
+    /// Creates a [`Context`] for items emitted on the current thread.
+    fn context(&self) -> Context<'_> {
+        Context {
+            depth: self.depth,
+            parent: self.parent.as_ref().map(|span| &span.metadata),
+            thread_id: self.thread_id,
+        }
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<LocalState> = RefCell::new(LocalState::default());
+}
 
 impl Subscriber {
+    /// Creates a new span, containing the given [`SpanMetadata`] instance,
+    /// without entering it.
+    ///
+    /// The span is closed once the returned [`Span`] handle, and all clones of
+    /// it, have been dropped. If the span is not enabled by the current
+    /// filter, a disabled span is returned.
+    pub fn new_span(&self, mut metadata: SpanMetadata) -> Span {
+        let interest = metadata.callsite.map_or(Interest::Sometimes, Callsite::interest);
+
+        if interest.is_never()
+            || (!interest.is_always() && self.filter().as_ref().is_some_and(|f| !f.span_enabled(&metadata)))
+        {
+            return Span::none();
+        }
+
+        let local = LOCAL.with_borrow(LocalState::snapshot);
+        let cx = local.context();
+
+        metadata.entered_at = Some(Instant::now());
+        metadata.thread_id = Some(cx.thread_id);
+
+        for sink in self.sinks().iter() {
+            if let Err(err) = sink.enter_span(&metadata, &cx) {
+                self.handle_error(err);
+            }
+        }
+
+        Span::new(metadata)
+    }
+
     /// Enter a new span, containing the given [`SpanMetadata`] instance.
     ///
     /// This method returns a guard for the span. When the guard is dropped,
@@ -164,49 +421,134 @@ impl Subscriber {
     #[must_use = "This function returns a guard object to exit the span.
         Dropping it immediately is probably incorrect. Make sure that the returned value
         lives until the span is exited."]
-    pub fn enter_span(&mut self, metadata: SpanMetadata) -> Option<SpanGuard> {
-        if self.filter.as_ref().is_some_and(|f| !f.span_enabled(&metadata)) {
-            return None;
-        }
-
-        let cx = RenderContext {
-            depth: self.depth,
-            level: metadata.level,
-        };
+    pub fn enter_span(&self, metadata: SpanMetadata) -> Option<SpanGuard> {
+        let span = self.new_span(metadata);
+        let inner = span.inner.as_ref()?;
 
-        let mut stdout = std::io::stdout();
-        metadata.render_to(&cx, &mut stdout).unwrap();
+        LOCAL.with_borrow_mut(|local| local.current.push_front(inner.clone()));
 
-        self.depth += 1;
-        self.current.push_front(metadata);
-
-        Some(SpanGuard)
+        Some(SpanGuard {
+            span,
+            _not_send: PhantomData,
+        })
     }
 
     /// Emit the given event in the current span.
     pub fn event(&self, metadata: EventMetadata) {
-        let current_span = self.current.front();
-
-        if self
-            .filter
-            .as_ref()
-            .is_some_and(|f| !f.event_enabled(&metadata, current_span))
-        {
+        let local = LOCAL.with_borrow(|local| {
+            let ancestors = local.current.iter().map(|span| &span.metadata);
+            let interest = metadata.callsite.map_or(Interest::Sometimes, Callsite::interest);
+
+            if interest.is_never()
+                || (!interest.is_always()
+                    && self
+                        .filter()
+                        .as_ref()
+                        .is_some_and(|f| !f.event_enabled(&metadata, ancestors)))
+            {
+                return None;
+            }
+
+            Some(local.snapshot())
+        });
+
+        let Some(local) = local else {
             return;
+        };
+
+        let cx = local.context();
+
+        for sink in self.sinks().iter() {
+            if let Err(err) = sink.event(&metadata, &cx) {
+                self.handle_error(err);
+            }
+        }
+    }
+
+    pub fn exit_span(&self, span: &SpanGuard) {
+        if let Some(inner) = &span.span.inner {
+            span::exit(inner);
         }
+    }
 
-        let cx = RenderContext {
-            depth: self.depth,
-            level: metadata.level,
+    /// Notifies all sinks that the span containing the given [`SpanMetadata`]
+    /// has been closed.
+    fn close_span(&self, metadata: &SpanMetadata) {
+        // The span might be closed while the thread is being torn down, in which
+        // case the stack is no longer available.
+        let Ok(local) = LOCAL.try_with(|local| local.borrow().snapshot()) else {
+            return;
         };
 
-        let mut stdout = std::io::stdout();
-        metadata.render_to(&cx, &mut stdout).unwrap();
+        let cx = local.context();
+
+        for sink in self.sinks().iter() {
+            if let Err(err) = sink.exit_span(metadata, &cx) {
+                self.handle_error(err);
+            }
+        }
+    }
+
+    /// Returns the number of I/O errors raised by sinks, which have been
+    /// counted by the current [`ErrorHandler`].
+    pub fn error_count(&self) -> u64 {
+        self.error_count.load(Ordering::Relaxed)
+    }
+
+    /// Handles an I/O error raised by a sink, using the current
+    /// [`ErrorHandler`].
+    fn handle_error(&self, err: std::io::Error) {
+        // The handler is cloned out of the lock, so custom handlers may freely
+        // emit spans and events, or replace the handler.
+        let handler = self.error_handler.read().unwrap_or_else(|err| err.into_inner()).clone();
+
+        match &*handler {
+            ErrorHandler::Ignore => {}
+            ErrorHandler::Count => {
+                self.error_count.fetch_add(1, Ordering::Relaxed);
+            }
+            ErrorHandler::ReportOnce => {
+                if self.error_count.fetch_add(1, Ordering::Relaxed) == 0 {
+                    // If `stderr` is also unavailable, there's nowhere left to report to.
+                    let _ = writeln!(
+                        std::io::stderr(),
+                        "libftrace: failed to write trace output: {err} (further errors are not reported)"
+                    );
+                }
+            }
+            ErrorHandler::Custom(f) => {
+                self.error_count.fetch_add(1, Ordering::Relaxed);
+                f(&err);
+            }
+        }
+    }
+
+    /// Returns the number of spans which are currently entered on the calling
+    /// thread.
+    pub fn depth(&self) -> usize {
+        LOCAL.with_borrow(|local| local.current.len())
     }
 
-    pub fn exit_span(&mut self, _span: &SpanGuard) {
-        self.current.pop_front();
-        self.depth -= 1;
+    /// Determines the interest of the current filter in the given [`Callsite`].
+    fn callsite_interest(&self, callsite: &Callsite) -> Interest {
+        match self.filter().as_ref() {
+            Some(filter) => filter.callsite_interest(callsite),
+            None => Interest::Always,
+        }
+    }
+
+    /// Gets a read guard for the currently active filter.
+    fn filter(&self) -> std::sync::RwLockReadGuard<'_, Option<EnvFilter>> {
+        self.filter.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Gets the currently installed sinks.
+    ///
+    /// The sinks are cloned out of the lock, so it isn't held while they run.
+    /// Since sinks may enter spans themselves, holding it would deadlock once
+    /// another thread is waiting to replace the sinks.
+    fn sinks(&self) -> Arc<[Arc<dyn Sink>]> {
+        self.sinks.read().unwrap_or_else(|err| err.into_inner()).clone()
     }
 }
 
@@ -219,6 +561,19 @@ pub enum Level {
     Error,
 }
 
+impl Level {
+    /// Gets the name of the level, in uppercase.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
 impl TryFrom<&str> for Level {
     type Error = ();
 
@@ -234,11 +589,123 @@ impl TryFrom<&str> for Level {
     }
 }
 
+/// The maximum verbosity of a filter directive.
+///
+/// This is either a [`Level`], which allows all items of that level or any
+/// less verbose level, or [`LevelFilter::Off`], which allows nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LevelFilter {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Off,
+}
+
+impl LevelFilter {
+    /// Determines whether the filter allows items of the given [`Level`].
+    pub const fn enables(&self, level: Level) -> bool {
+        // Both enums list their levels in the same order, so their
+        // discriminants can be compared directly.
+        *self as u8 <= level as u8
+    }
+
+    /// Gets the name of the level filter, in uppercase.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LevelFilter::Trace => "TRACE",
+            LevelFilter::Debug => "DEBUG",
+            LevelFilter::Info => "INFO",
+            LevelFilter::Warn => "WARN",
+            LevelFilter::Error => "ERROR",
+            LevelFilter::Off => "OFF",
+        }
+    }
+}
+
+/// The maximum level of spans and events which are compiled into the program,
+/// as configured by the `max_level_*` and `release_max_level_*` features.
+///
+/// Spans and events above this level are removed at compile time, regardless of
+/// any filters. For example, enabling `release_max_level_warn` removes all
+/// `trace`, `debug` and `info` spans and events from builds without debug
+/// assertions. If multiple features are enabled, the most restrictive one is
+/// used, while `release_max_level_*` features take precedence over
+/// `max_level_*` features in release builds.
+pub const STATIC_MAX_LEVEL: LevelFilter = static_max_level();
+
+const fn static_max_level() -> LevelFilter {
+    if !cfg!(debug_assertions) {
+        if cfg!(feature = "release_max_level_off") {
+            return LevelFilter::Off;
+        } else if cfg!(feature = "release_max_level_error") {
+            return LevelFilter::Error;
+        } else if cfg!(feature = "release_max_level_warn") {
+            return LevelFilter::Warn;
+        } else if cfg!(feature = "release_max_level_info") {
+            return LevelFilter::Info;
+        } else if cfg!(feature = "release_max_level_debug") {
+            return LevelFilter::Debug;
+        } else if cfg!(feature = "release_max_level_trace") {
+            return LevelFilter::Trace;
+        }
+    }
+
+    if cfg!(feature = "max_level_off") {
+        LevelFilter::Off
+    } else if cfg!(feature = "max_level_error") {
+        LevelFilter::Error
+    } else if cfg!(feature = "max_level_warn") {
+        LevelFilter::Warn
+    } else if cfg!(feature = "max_level_info") {
+        LevelFilter::Info
+    } else if cfg!(feature = "max_level_debug") {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Trace
+    }
+}
+
+impl From<Level> for LevelFilter {
+    fn from(value: Level) -> Self {
+        match value {
+            Level::Trace => LevelFilter::Trace,
+            Level::Debug => LevelFilter::Debug,
+            Level::Info => LevelFilter::Info,
+            Level::Warn => LevelFilter::Warn,
+            Level::Error => LevelFilter::Error,
+        }
+    }
+}
+
+impl TryFrom<&str> for LevelFilter {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, ()> {
+        if value.eq_ignore_ascii_case("off") {
+            return Ok(LevelFilter::Off);
+        }
+
+        Level::try_from(value).map(LevelFilter::from)
+    }
+}
+
 pub struct SpanMetadata {
+    /// The name of the span, which is shown by sinks.
     pub name: &'static str,
+
+    /// The target of the span, which is matched by filters. Unless overridden,
+    /// this is the same as the name of the span.
+    pub target: &'static str,
+
     pub location: &'static std::panic::Location<'static>,
     pub level: Level,
     fields: FieldSet,
+    callsite: Option<&'static Callsite>,
+    entered_at: Option<Instant>,
+    thread_id: Option<u64>,
+    return_value: OnceLock<Value>,
 }
 
 impl SpanMetadata {
@@ -246,23 +713,81 @@ impl SpanMetadata {
     pub fn new(name: &'static str, level: Level) -> Self {
         Self {
             name,
+            target: name,
             level,
             location: std::panic::Location::caller(),
             fields: FieldSet::default(),
+            callsite: None,
+            entered_at: None,
+            thread_id: None,
+            return_value: OnceLock::new(),
         }
     }
 
-    pub fn with_field(mut self, key: &'static str, value: impl Display + 'static) -> Self {
+    pub fn with_target(mut self, target: &'static str) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_field(mut self, key: &'static str, value: impl ToValue) -> Self {
         self.fields.add(key, value);
         self
     }
+
+    /// Attaches the static [`Callsite`] which created the span, so the cached
+    /// interest of the callsite can be used instead of evaluating the filter.
+    pub fn with_callsite(mut self, callsite: &'static Callsite) -> Self {
+        self.callsite = Some(callsite);
+        self
+    }
+
+    /// Gets the [`Callsite`] which created the span, if any.
+    pub fn callsite(&self) -> Option<&'static Callsite> {
+        self.callsite
+    }
+
+    /// Returns an iterator of all fields attached to the span.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        self.fields.iter()
+    }
+
+    /// Gets the instant at which the span was entered, if it has been entered.
+    pub fn entered_at(&self) -> Option<Instant> {
+        self.entered_at
+    }
+
+    /// Gets the ID of the thread which the span was entered on, if it has been
+    /// entered.
+    ///
+    /// See [`Context::thread_id`] for more information.
+    pub fn thread_id(&self) -> Option<u64> {
+        self.thread_id
+    }
+
+    /// Gets the amount of time which has elapsed since the span was entered.
+    ///
+    /// If the span has not been entered, returns [`Duration::ZERO`].
+    pub fn elapsed(&self) -> Duration {
+        self.entered_at.map(|at| at.elapsed()).unwrap_or_default()
+    }
+
+    /// Gets the return value of the span, if one has been recorded.
+    pub fn return_value(&self) -> Option<&Value> {
+        self.return_value.get()
+    }
 }
 
 pub struct EventMetadata {
     pub message: String,
+
+    /// The target of the event, which is the path of the module it was
+    /// emitted from, unless overridden. Empty if no target was given.
+    pub target: &'static str,
+
     pub location: &'static std::panic::Location<'static>,
     pub level: Level,
     fields: FieldSet,
+    callsite: Option<&'static Callsite>,
 }
 
 impl EventMetadata {
@@ -270,16 +795,40 @@ impl EventMetadata {
     pub fn new<S: Into<String>>(message: S, level: Level) -> Self {
         Self {
             message: message.into(),
+            target: "",
             level,
             location: std::panic::Location::caller(),
             fields: FieldSet::default(),
+            callsite: None,
         }
     }
 
-    pub fn with_field(mut self, key: &'static str, value: impl Display + 'static) -> Self {
+    pub fn with_target(mut self, target: &'static str) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_field(mut self, key: &'static str, value: impl ToValue) -> Self {
         self.fields.add(key, value);
         self
     }
+
+    /// Attaches the static [`Callsite`] which created the event, so the cached
+    /// interest of the callsite can be used instead of evaluating the filter.
+    pub fn with_callsite(mut self, callsite: &'static Callsite) -> Self {
+        self.callsite = Some(callsite);
+        self
+    }
+
+    /// Gets the [`Callsite`] which created the event, if any.
+    pub fn callsite(&self) -> Option<&'static Callsite> {
+        self.callsite
+    }
+
+    /// Returns an iterator of all fields attached to the event.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        self.fields.iter()
+    }
 }
 
 #[derive(Default)]
@@ -288,21 +837,40 @@ struct FieldSet {
 }
 
 impl FieldSet {
-    pub fn add(&mut self, key: &'static str, value: impl Display + 'static) {
-        self.inner.push((key, Value(Box::new(value))));
+    pub fn add(&mut self, key: &'static str, value: impl ToValue) {
+        self.inner.push((key, value.to_value()));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        self.inner.iter().map(|(key, value)| (*key, value))
     }
 }
 
-pub struct Value(Box<dyn Display>);
+/// A guard which keeps a span entered on the current thread, returned by
+/// [`Subscriber::enter_span`].
+///
+/// When the guard is dropped, the span is exited and closed.
+pub struct SpanGuard {
+    span: Span,
 
-impl Display for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(self.0.as_ref(), f)
-    }
+    /// The span is entered on the stack of the current thread, so it must also
+    /// be exited from the same thread.
+    _not_send: PhantomData<*const ()>,
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct SpanGuard;
+impl SpanGuard {
+    /// Gets the span which is entered by the guard.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Records the given value as the return value of the entered span.
+    ///
+    /// See [`Span::record_return`] for more information.
+    pub fn record_return(&self, value: impl ToValue) {
+        self.span.record_return(value);
+    }
+}
 
 impl Drop for SpanGuard {
     fn drop(&mut self) {
@@ -310,26 +878,75 @@ impl Drop for SpanGuard {
     }
 }
 
-struct Global<T> {
-    inner: UnsafeCell<T>,
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::value::{DebugValue, DisplayValue, ViaDebug, ViaDisplay, ViaToValue};
 }
 
-unsafe impl<T> Sync for Global<T> where T: Send {}
+static GLOBAL: OnceLock<Subscriber> = OnceLock::new();
 
-static GLOBAL: OnceLock<Global<Subscriber>> = OnceLock::new();
-
-pub fn with_subscriber<F: FnOnce(&mut Subscriber) -> R, R>(f: F) -> R {
-    let global = GLOBAL.get_or_init(|| Global {
-        inner: UnsafeCell::new(Subscriber::default()),
-    });
-
-    unsafe { f(&mut *global.inner.get()) }
+/// Invokes the given closure with a reference to the global trace subscriber.
+pub fn with_subscriber<F: FnOnce(&Subscriber) -> R, R>(f: F) -> R {
+    f(GLOBAL.get_or_init(Subscriber::default))
 }
 
 /// Sets the current filter of the global trace subscriber.
 ///
+/// The filter is shared between all threads, so it applies to all spans and
+/// events emitted after this call, regardless of which thread emits them.
+///
 /// To create a [`EnvFilter`] instance, see [`from_env`], [`from_default_env`]
-/// or [`parse`].
+/// or [`parse`]. To replace the filter from another part of the program, such
+/// as a signal handler, see [`reload_handle`].
 pub fn set_filter(filter: EnvFilter) {
-    with_subscriber(|subscriber| subscriber.filter = Some(filter));
+    reload_handle().reload(filter);
+}
+
+/// Replaces all sinks of the global trace subscriber with the given [`Sink`].
+///
+/// By default, the subscriber writes to `stdout` using a [`PrettySink`]. To
+/// write to some other destination, such as `stderr` or a file, replace it
+/// using this function:
+/// ```
+/// libftrace::set_sink(libftrace::PrettySink::stderr());
+/// ```
+pub fn set_sink<S: Sink + 'static>(sink: S) {
+    with_subscriber(|subscriber| {
+        *subscriber.sinks.write().unwrap_or_else(|err| err.into_inner()) = Arc::new([Arc::new(sink)]);
+    });
+}
+
+/// Replaces all sinks of the global trace subscriber with a single sink, which
+/// writes to `stdout` in the given [`Format`].
+///
+/// By default, the format is read from the [`FORMAT_ENV`] environment variable,
+/// falling back to [`Format::Pretty`].
+pub fn set_format(format: Format) {
+    with_subscriber(|subscriber| {
+        *subscriber.sinks.write().unwrap_or_else(|err| err.into_inner()) = Arc::new([format.stdout_sink().into()]);
+    });
+}
+
+/// Sets how I/O errors raised by the sinks of the global trace subscriber are
+/// handled.
+///
+/// By default, the first error is reported to `stderr` and all further errors
+/// are counted. See [`ErrorHandler`] for all options.
+pub fn set_error_handler(handler: ErrorHandler) {
+    with_subscriber(|subscriber| {
+        *subscriber.error_handler.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(handler);
+    });
+}
+
+/// Adds the given [`Sink`] to the global trace subscriber, alongside all
+/// existing sinks.
+///
+/// All spans and events which pass the filter of the subscriber are sent to
+/// every installed sink, in the order they were added.
+pub fn add_sink<S: Sink + 'static>(sink: S) {
+    with_subscriber(|subscriber| {
+        let mut sinks = subscriber.sinks.write().unwrap_or_else(|err| err.into_inner());
+
+        *sinks = sinks.iter().cloned().chain([Arc::new(sink) as Arc<dyn Sink>]).collect();
+    });
 }
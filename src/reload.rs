@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use crate::filter::{self, EnvFilter, ParseError};
+use crate::{callsite, with_subscriber};
+
+/// A handle for replacing the filter of the global trace subscriber while the
+/// program is running.
+///
+/// Reload handles can be freely copied and sent to other threads. Replacing the
+/// filter itself is atomic, but the cached interest of each [`Callsite`] is
+/// only recomputed afterwards. Until [`ReloadHandle::reload`] returns, spans
+/// and events from callsites which the old filter always or never enabled may
+/// still be decided by the old filter.
+///
+/// [`Callsite`]: crate::Callsite
+///
+/// ```
+/// let handle = libftrace::reload_handle();
+///
+/// // Somewhere else, such as a signal handler or an admin endpoint:
+/// handle.reload(libftrace::filter::parse("backend=trace").unwrap());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ReloadHandle {
+    _private: (),
+}
+
+/// Gets a [`ReloadHandle`] for the filter of the global trace subscriber.
+pub fn reload_handle() -> ReloadHandle {
+    ReloadHandle { _private: () }
+}
+
+impl ReloadHandle {
+    /// Replaces the current filter with the given [`EnvFilter`].
+    pub fn reload(&self, filter: EnvFilter) {
+        with_subscriber(|subscriber| {
+            *subscriber.filter.write().unwrap_or_else(|err| err.into_inner()) = Some(filter);
+        });
+
+        // The cached interest of all callsites depends on the filter.
+        callsite::rebuild_interest();
+    }
+
+    /// Re-reads the filter from the given environment variable, using the same
+    /// rules as [`filter::from_env`].
+    ///
+    /// If the filter fails to parse, the current filter is kept.
+    pub fn reload_from_env(&self, env_name: &str) -> Result<(), ParseError> {
+        self.reload(filter::from_env(env_name)?);
+
+        Ok(())
+    }
+
+    /// Re-reads the filter from the file at the given path.
+    ///
+    /// Each line of the file may contain one or more comma-separated
+    /// directives, optionally followed by a trailing comma. Empty lines and
+    /// lines starting with `#` are ignored. If the file is empty, a default
+    /// [`EnvFilter`] is used, the same as for [`filter::from_env`].
+    ///
+    /// If the file cannot be read or the filter fails to parse, the current
+    /// filter is kept.
+    pub fn reload_from_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ReloadError> {
+        let contents = std::fs::read_to_string(path).map_err(ReloadError::Io)?;
+        let mut combined: Option<EnvFilter> = None;
+
+        for (idx, line) in contents.lines().enumerate() {
+            let indent = line.len() - line.trim_start().len();
+            let directives = line.trim().trim_end_matches(',').trim_end();
+
+            if directives.is_empty() || directives.starts_with('#') {
+                continue;
+            }
+
+            // Errors are reported relative to the line, so they point to the
+            // right place within the file.
+            let parsed = filter::parse(directives).map_err(|err| ReloadError::Parse {
+                line: idx + 1,
+                column: line[..indent + err.offset].chars().count() + 1,
+                err,
+            })?;
+
+            combined = Some(match combined {
+                Some(combined) => combined.merge(parsed),
+                None => parsed,
+            });
+        }
+
+        self.reload(combined.unwrap_or_default());
+
+        Ok(())
+    }
+}
+
+/// An error raised while reloading a filter from a file, using
+/// [`ReloadHandle::reload_from_file`].
+#[derive(Debug)]
+pub enum ReloadError {
+    /// The file could not be read.
+    Io(std::io::Error),
+
+    /// A line of the file could not be parsed.
+    Parse {
+        /// The line of the file which contains the error, starting from 1.
+        line: usize,
+
+        /// The column of the error within the line, in characters, starting
+        /// from 1.
+        column: usize,
+
+        /// The error raised while parsing the line, whose offsets are relative
+        /// to the start of the directives on the line.
+        err: ParseError,
+    },
+}
+
+impl std::fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReloadError::Io(err) => write!(f, "failed to read filter: {err}"),
+            ReloadError::Parse { line, column, err } => write!(
+                f,
+                "invalid filter directive `{}` at line {line}, column {column}: {}",
+                err.directive, err.kind
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReloadError::Io(err) => Some(err),
+            ReloadError::Parse { err, .. } => Some(err),
+        }
+    }
+}
@@ -46,6 +46,33 @@ impl Renderable for SpanMetadata {
     }
 }
 
+/// Renders the exit record of a span, containing the time elapsed since it was
+/// entered, as well as the return value of the span, if one was recorded.
+pub(crate) struct SpanExit<'a>(pub &'a SpanMetadata);
+
+impl Renderable for SpanExit<'_> {
+    fn render_to(&self, cx: &RenderContext, f: &mut dyn Write) -> std::io::Result<()> {
+        let span = self.0;
+
+        cx.write_ident(f)?;
+
+        time::UtcDateTime::now().render_to(cx, f)?;
+        write!(f, " ")?;
+
+        span.level.render_to(cx, f)?;
+        writeln!(f, "  {} {} {:?}", span.name, "exited after".dimmed(), span.elapsed())?;
+
+        if let Some(value) = span.return_value() {
+            cx.write_gutter(f)?;
+            writeln!(f, "{} {}", "returned".dimmed(), with_level_styling(cx.level, value))?;
+        }
+
+        writeln!(f)?;
+
+        Ok(())
+    }
+}
+
 impl Renderable for EventMetadata {
     fn render_to(&self, cx: &RenderContext, f: &mut dyn Write) -> std::io::Result<()> {
         cx.write_ident(f)?;
@@ -78,10 +105,10 @@ impl Renderable for FieldSet {
         write!(f, "{} ", "with".dimmed())?;
 
         for (idx, (key, value)) in self.inner.iter().enumerate() {
-            print!("{}", with_level_styling(cx.level, format!("{key}: {value}")));
+            write!(f, "{}", with_level_styling(cx.level, format!("{key}: {value}")))?;
 
             if idx < field_len - 1 {
-                print!("{}", ", ".dimmed());
+                write!(f, "{}", ", ".dimmed())?;
             }
         }
 
@@ -91,22 +118,14 @@ impl Renderable for FieldSet {
 
 impl Renderable for Level {
     fn render_to(&self, _cx: &RenderContext, f: &mut dyn Write) -> std::io::Result<()> {
-        let text = match self {
-            Level::Trace => "TRACE",
-            Level::Debug => "DEBUG",
-            Level::Info => "INFO",
-            Level::Warn => "WARN",
-            Level::Error => "ERROR",
-        };
-
-        write!(f, "{}", with_level_styling(*self, text))
+        write!(f, "{}", with_level_styling(*self, self.as_str()))
     }
 }
 
 impl Renderable for time::UtcDateTime {
     fn render_to(&self, _cx: &RenderContext, f: &mut dyn Write) -> std::io::Result<()> {
         let format = time::format_description::well_known::Rfc3339;
-        let display = self.format(&format).unwrap();
+        let display = self.format(&format).map_err(std::io::Error::other)?;
 
         write!(f, "{}", display.dimmed())
     }
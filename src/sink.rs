@@ -0,0 +1,212 @@
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::render::{RenderContext, Renderable, SpanExit};
+use crate::{EventMetadata, JsonSink, SpanMetadata};
+
+/// A destination for spans and events, which receives a notification whenever
+/// a span is entered or exited, or when an event is emitted.
+///
+/// Sinks are installed on the global subscriber using [`set_sink`] or
+/// [`add_sink`]. By default, the subscriber contains a single
+/// [`PrettySink`], which writes coloured, human-readable text to `stdout`.
+///
+/// Since the subscriber is shared between all threads, sinks may be notified
+/// from multiple threads at once.
+///
+/// [`set_sink`]: crate::set_sink
+/// [`add_sink`]: crate::add_sink
+pub trait Sink: Send + Sync {
+    /// Invoked when a span has been entered, after it has passed the filter
+    /// of the subscriber.
+    fn enter_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()>;
+
+    /// Invoked when a span is exited, after all of its child spans have been
+    /// exited.
+    fn exit_span(&self, _span: &SpanMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Invoked when an event is emitted, after it has passed the filter of the
+    /// subscriber.
+    fn event(&self, event: &EventMetadata, cx: &Context<'_>) -> std::io::Result<()>;
+}
+
+/// Defines how I/O errors raised by [`Sink`]s are handled by the subscriber.
+///
+/// Tracing should never crash the program being traced, so errors raised while
+/// writing spans or events are never propagated. Instead, they're passed to the
+/// error handler of the subscriber, which is set using [`set_error_handler`].
+///
+/// [`set_error_handler`]: crate::set_error_handler
+#[derive(Default)]
+pub enum ErrorHandler {
+    /// Silently discard all errors.
+    Ignore,
+
+    /// Count all errors, which can be retrieved using
+    /// [`Subscriber::error_count`].
+    ///
+    /// [`Subscriber::error_count`]: crate::Subscriber::error_count
+    Count,
+
+    /// Count all errors and report the first one to `stderr`.
+    #[default]
+    ReportOnce,
+
+    /// Count all errors and pass them to the given function.
+    Custom(Box<dyn Fn(&std::io::Error) + Send + Sync>),
+}
+
+/// Contextual information about where a span or event was emitted, given to
+/// [`Sink`]s alongside the item itself.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    /// The number of spans which are entered on the current thread, not
+    /// counting the span which is being entered or exited.
+    pub depth: usize,
+
+    /// The innermost span which the item was emitted within, if any.
+    pub parent: Option<&'a SpanMetadata>,
+
+    /// A unique ID of the current thread.
+    ///
+    /// Thread IDs are assigned sequentially, starting from 1, in the order
+    /// which threads first interact with the subscriber.
+    pub thread_id: u64,
+}
+
+/// Defines the environment variable which is read by [`Format::from_env`].
+pub const FORMAT_ENV: &str = "LIBFTRACE_FORMAT";
+
+/// Output formats which are built into `libftrace`.
+///
+/// The format of the global subscriber can be selected at runtime by setting
+/// the [`FORMAT_ENV`] environment variable to the name of the format, such as
+/// `LIBFTRACE_FORMAT=json`, or by calling [`set_format`].
+///
+/// [`set_format`]: crate::set_format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Coloured, human-readable text, written by [`PrettySink`].
+    #[default]
+    Pretty,
+
+    /// JSON objects, one per line, written by [`JsonSink`].
+    Json,
+}
+
+impl Format {
+    /// Reads the value of the [`FORMAT_ENV`] environment variable and parses it
+    /// into a [`Format`].
+    ///
+    /// If the environment variable is unset or contains an unknown format,
+    /// returns `None`.
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(FORMAT_ENV).ok()?;
+
+        Format::try_from(value.as_str()).ok()
+    }
+
+    /// Creates a new [`Sink`] which writes to `stdout` in the current format.
+    pub fn stdout_sink(self) -> Box<dyn Sink> {
+        match self {
+            Format::Pretty => Box::new(PrettySink::stdout()),
+            Format::Json => Box::new(JsonSink::stdout()),
+        }
+    }
+}
+
+impl TryFrom<&str> for Format {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, ()> {
+        match value.to_lowercase().as_str() {
+            "pretty" => Ok(Format::Pretty),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A [`Sink`] which writes coloured, human-readable text to some writer.
+///
+/// Each span and event is written in its entirety while holding a lock over
+/// the writer, so items from different threads are never interleaved.
+pub struct PrettySink<W: Write + Send> {
+    writer: Mutex<W>,
+    span_exits: bool,
+}
+
+impl<W: Write + Send> PrettySink<W> {
+    /// Creates a new [`PrettySink`] which writes to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            span_exits: true,
+        }
+    }
+
+    /// Sets whether to write a record when a span is exited, containing the
+    /// time elapsed within the span and its return value, if any.
+    ///
+    /// Defaults to `true`.
+    pub fn with_span_exits(mut self, enabled: bool) -> Self {
+        self.span_exits = enabled;
+        self
+    }
+
+    fn render<R: Renderable>(&self, item: &R, cx: &RenderContext) -> std::io::Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|err| err.into_inner());
+
+        item.render_to(cx, &mut *writer)?;
+        writer.flush()
+    }
+}
+
+impl PrettySink<std::io::Stdout> {
+    /// Creates a new [`PrettySink`] which writes to `stdout`.
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl PrettySink<std::io::Stderr> {
+    /// Creates a new [`PrettySink`] which writes to `stderr`.
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+    }
+}
+
+impl<W: Write + Send> Sink for PrettySink<W> {
+    fn enter_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        let cx = RenderContext {
+            depth: cx.depth,
+            level: span.level,
+        };
+
+        self.render(span, &cx)
+    }
+
+    fn exit_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        if !self.span_exits {
+            return Ok(());
+        }
+
+        let cx = RenderContext {
+            depth: cx.depth,
+            level: span.level,
+        };
+
+        self.render(&SpanExit(span), &cx)
+    }
+
+    fn event(&self, event: &EventMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        let cx = RenderContext {
+            depth: cx.depth,
+            level: event.level,
+        };
+
+        self.render(event, &cx)
+    }
+}
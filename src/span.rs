@@ -0,0 +1,190 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+
+use crate::{LOCAL, SpanMetadata, ToValue, with_subscriber};
+
+/// A handle to a span, which can be entered and exited any number of times,
+/// from any thread.
+///
+/// Spans are created using the [`span!`] macro, by `#[traced]` or using
+/// [`Subscriber::new_span`]. Creating a span notifies
+/// all sinks that the span has been entered, but it does not make it the parent
+/// of new spans or events. To do that, the span must be entered using
+/// [`Span::enter`], which is usually done for the duration of a single
+/// function or, for asynchronous functions, a single poll of the future.
+///
+/// Cloning a [`Span`] returns a new handle to the same span. The span is closed
+/// once all handles to it have been dropped.
+///
+/// ```
+/// use libftrace::*;
+///
+/// let span = span!(Level::Info, "batch", size = 3);
+///
+/// for item in 0..3 {
+///     span.in_scope(|| info!("processing item", item = item));
+/// }
+/// ```
+///
+/// [`span!`]: crate::span!
+/// [`Subscriber::new_span`]: crate::Subscriber::new_span
+#[derive(Clone, Default)]
+pub struct Span {
+    pub(crate) inner: Option<Arc<SpanInner>>,
+}
+
+pub(crate) struct SpanInner {
+    pub(crate) metadata: SpanMetadata,
+}
+
+impl Drop for SpanInner {
+    fn drop(&mut self) {
+        with_subscriber(|subscriber| subscriber.close_span(&self.metadata));
+    }
+}
+
+impl Span {
+    pub(crate) fn new(metadata: SpanMetadata) -> Self {
+        Self {
+            inner: Some(Arc::new(SpanInner { metadata })),
+        }
+    }
+
+    /// Creates a new disabled span, which never emits anything to the
+    /// subscriber.
+    pub fn none() -> Self {
+        Self { inner: None }
+    }
+
+    /// Determines whether the span is disabled, either because it was created
+    /// using [`Span::none`] or because it was filtered out.
+    pub fn is_disabled(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Gets the metadata of the span, if it is enabled.
+    pub fn metadata(&self) -> Option<&SpanMetadata> {
+        self.inner.as_ref().map(|inner| &inner.metadata)
+    }
+
+    /// Enters the span on the current thread, making it the parent of any spans
+    /// or events created until the returned guard is dropped.
+    ///
+    /// Exiting the span does not close it - the span is only closed once all
+    /// handles to it have been dropped.
+    #[must_use = "The span is exited when the returned guard is dropped."]
+    pub fn enter(&self) -> Entered<'_> {
+        if let Some(inner) = &self.inner {
+            LOCAL.with_borrow_mut(|local| local.current.push_front(inner.clone()));
+        }
+
+        Entered {
+            span: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Enters the span on the current thread for the duration of the given
+    /// closure, returning its result.
+    ///
+    /// This is equivalent to holding the guard returned by [`Span::enter`] for
+    /// the duration of the closure.
+    pub fn in_scope<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        let _entered = self.enter();
+        f()
+    }
+
+    /// Records the given value as the return value of the span, which is given
+    /// to all sinks when the span is closed.
+    ///
+    /// Only the first recorded return value is kept.
+    pub fn record_return(&self, value: impl ToValue) {
+        if let Some(inner) = &self.inner {
+            let _ = inner.metadata.return_value.set(value.to_value());
+        }
+    }
+}
+
+/// Removes the given span from the stack of entered spans on the current
+/// thread.
+///
+/// Since asynchronous spans may be exited in a different order than they were
+/// entered, the innermost entry of the span is removed, instead of the front of
+/// the stack.
+pub(crate) fn exit(inner: &Arc<SpanInner>) {
+    let removed = LOCAL.with_borrow_mut(|local| {
+        let idx = local.current.iter().position(|span| Arc::ptr_eq(span, inner))?;
+
+        local.current.remove(idx)
+    });
+
+    // If this was the last handle to the span, it will be closed when dropped,
+    // which needs to borrow the stack again.
+    drop(removed);
+}
+
+/// A guard which keeps a span entered on the current thread, returned by
+/// [`Span::enter`].
+///
+/// When the guard is dropped, the span is exited.
+pub struct Entered<'a> {
+    span: &'a Span,
+
+    /// Keeps the guard on the current thread, the same as for
+    /// [`crate::SpanGuard`].
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        if let Some(inner) = &self.span.inner {
+            exit(inner);
+        }
+    }
+}
+
+/// Extension trait for attaching a [`Span`] to a [`Future`].
+///
+/// This is what `#[traced]` uses for `async fn`s, but it can also be used to
+/// instrument any other future.
+pub trait Instrument: Sized {
+    /// Instruments the future with the given [`Span`], entering the span every
+    /// time the future is polled and exiting it once the poll returns.
+    fn instrument(self, span: Span) -> Instrumented<Self> {
+        Instrumented { inner: self, span }
+    }
+}
+
+impl<F: Future> Instrument for F {}
+
+/// A [`Future`] which has been instrumented with a [`Span`].
+///
+/// See [`Instrument::instrument`] for more information.
+pub struct Instrumented<F> {
+    inner: F,
+    span: Span,
+}
+
+impl<F> Instrumented<F> {
+    /// Gets the span which the future is instrumented with.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `inner` is never moved out of `self`, and `span` is never
+        // pinned, so this is effectively a pin projection.
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
+
+        let _entered = this.span.enter();
+        inner.poll(cx)
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::{Debug, Display};
+
+/// The value of a field, attached to a span or event.
+///
+/// Primitive values, such as numbers, booleans and strings, keep their type,
+/// so they can be compared by filters and written as their native type by
+/// structured sinks, such as [`JsonSink`]. All other values are formatted
+/// when the field is created, using either their [`Display`] or [`Debug`]
+/// implementation.
+///
+/// [`JsonSink`]: crate::JsonSink
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+
+    /// A value which was formatted using its [`Debug`] implementation.
+    Debug(String),
+
+    /// A value which was formatted using its [`Display`] implementation.
+    Display(String),
+
+    /// An error, formatted along with all of its sources.
+    Error(String),
+}
+
+impl Value {
+    /// Creates a new [`Value`] from the [`Debug`] representation of the given
+    /// value.
+    pub fn debug<T: Debug + ?Sized>(value: &T) -> Self {
+        Value::Debug(format!("{value:?}"))
+    }
+
+    /// Creates a new [`Value`] from the [`Display`] representation of the given
+    /// value.
+    pub fn display<T: Display + ?Sized>(value: &T) -> Self {
+        Value::Display(value.to_string())
+    }
+
+    /// Creates a new [`Value`] from the given error, including the messages of
+    /// all of its sources, separated by colons.
+    pub fn error(error: &(dyn std::error::Error + 'static)) -> Self {
+        let mut message = error.to_string();
+        let mut source = error.source();
+
+        while let Some(err) = source {
+            message.push_str(": ");
+            message.push_str(&err.to_string());
+
+            source = err.source();
+        }
+
+        Value::Error(message)
+    }
+
+    /// Gets the value as a string slice, if the value is textual.
+    ///
+    /// Returns `None` for booleans and numbers.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) | Value::Debug(s) | Value::Display(s) | Value::Error(s) => Some(s),
+            Value::Bool(_) | Value::I64(_) | Value::U64(_) | Value::F64(_) => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(value) => Display::fmt(value, f),
+            Value::I64(value) => Display::fmt(value, f),
+            Value::U64(value) => Display::fmt(value, f),
+            Value::F64(value) => Display::fmt(value, f),
+            Value::Str(value) | Value::Debug(value) | Value::Display(value) | Value::Error(value) => {
+                Display::fmt(value, f)
+            }
+        }
+    }
+}
+
+/// Conversion of a borrowed value into a [`Value`].
+///
+/// This trait is implemented for all primitive types which have a matching
+/// [`Value`] variant, so they can be passed directly as fields. To use other
+/// types as fields, use [`Value::debug`] or [`Value::display`].
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Value {
+        Value::Str(self.to_owned())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::Str(self.clone())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+macro_rules! impl_value {
+    ($variant:ident($as:ty): $($ty:ty),+) => {
+        $(
+            impl ToValue for $ty {
+                fn to_value(&self) -> Value {
+                    Value::$variant(*self as $as)
+                }
+            }
+
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value as $as)
+                }
+            }
+        )+
+    };
+}
+
+impl_value!(Bool(bool): bool);
+impl_value!(I64(i64): i8, i16, i32, i64, isize);
+impl_value!(U64(u64): u8, u16, u32, u64, usize);
+impl_value!(F64(f64): f32, f64);
+
+impl ToValue for char {
+    fn to_value(&self) -> Value {
+        Value::Str(self.to_string())
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::Str(value.to_string())
+    }
+}
+
+/// Wrapper used by the event macros to convert field values, preferring
+/// [`ToValue`] and falling back to [`Display`].
+#[doc(hidden)]
+pub struct DisplayValue<'a, T: ?Sized>(pub &'a T);
+
+/// Wrapper used by `#[traced]` to convert field values, preferring [`ToValue`]
+/// and falling back to [`Debug`].
+#[doc(hidden)]
+pub struct DebugValue<'a, T: ?Sized>(pub &'a T);
+
+// The conversions below use autoref-based specialization: since method
+// resolution tries the receiver type by-value before auto-referencing it, the
+// impls for `DisplayValue<T>` and `DebugValue<T>` are preferred over the impls
+// for `&DisplayValue<T>` and `&DebugValue<T>`, when they apply.
+
+#[doc(hidden)]
+pub trait ViaToValue {
+    fn __value(&self) -> Value;
+}
+
+impl<T: ToValue + ?Sized> ViaToValue for DisplayValue<'_, T> {
+    fn __value(&self) -> Value {
+        self.0.to_value()
+    }
+}
+
+impl<T: ToValue + ?Sized> ViaToValue for DebugValue<'_, T> {
+    fn __value(&self) -> Value {
+        self.0.to_value()
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaDisplay {
+    fn __value(&self) -> Value;
+}
+
+impl<T: Display + ?Sized> ViaDisplay for &DisplayValue<'_, T> {
+    fn __value(&self) -> Value {
+        Value::display(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaDebug {
+    fn __value(&self) -> Value;
+}
+
+impl<T: Debug + ?Sized> ViaDebug for &DebugValue<'_, T> {
+    fn __value(&self) -> Value {
+        Value::debug(self.0)
+    }
+}
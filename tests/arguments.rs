@@ -0,0 +1,67 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Type which doesn't implement `Debug`, so it must be skipped.
+struct Connection;
+
+#[derive(Debug)]
+struct Request {
+    method: &'static str,
+}
+
+#[traced]
+fn all_arguments(id: u64, name: &str, req: Request) {
+    let _ = (id, name, req);
+}
+
+#[traced(skip(conn))]
+fn skipped_argument(conn: Connection, id: u64) {
+    let _ = (conn, id);
+}
+
+#[traced(skip_all, fields(method = req.method))]
+fn all_skipped(conn: Connection, req: Request) {
+    let _ = (conn, req);
+}
+
+#[traced(fields(id = id * 2))]
+fn shadowed_argument((id, _): (u64, u64)) {
+    let _ = id;
+}
+
+#[test]
+fn arguments_are_recorded_unless_skipped() {
+    let sink = RecordingSink::default();
+
+    set_sink(sink.clone());
+    set_filter(filter::parse("trace").unwrap());
+
+    all_arguments(1, "alice", Request { method: "GET" });
+    skipped_argument(Connection, 2);
+    all_skipped(Connection, Request { method: "POST" });
+    shadowed_argument((3, 4));
+
+    let spans = sink.entered();
+    let spans = spans
+        .iter()
+        .map(|span| (span.name, span.fields.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(spans, vec![
+        ("arguments::all_arguments", vec![
+            ("id", Value::U64(1)),
+            ("name", Value::Str(String::from("alice"))),
+            ("req", Value::Debug(String::from("Request { method: \"GET\" }"))),
+        ]),
+        ("arguments::skipped_argument", vec![("id", Value::U64(2))]),
+        ("arguments::all_skipped", vec![(
+            "method",
+            Value::Str(String::from("POST"))
+        )]),
+        ("arguments::shadowed_argument", vec![("id", Value::U64(6))]),
+    ]);
+}
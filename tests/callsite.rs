@@ -0,0 +1,99 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::RecordingSink;
+use libftrace::filter::parse;
+use libftrace::*;
+
+#[test]
+fn interest_is_computed_from_directives() {
+    let span = Callsite::span("backend::api::handle", Level::Debug, &["user"]);
+    let event = Callsite::event("backend::db", Level::Debug, &["rows"]);
+
+    let cases = [
+        ("info", Interest::Never, Interest::Never),
+        ("debug", Interest::Always, Interest::Always),
+        ("info,backend=debug", Interest::Always, Interest::Always),
+        ("info,backend::db=debug", Interest::Never, Interest::Always),
+        ("info,frontend=debug", Interest::Never, Interest::Sometimes),
+        ("debug,-backend::api", Interest::Never, Interest::Sometimes),
+        (
+            "info,backend[user=alice]=debug",
+            Interest::Sometimes,
+            Interest::Sometimes,
+        ),
+        ("info,backend[id=1]=debug", Interest::Never, Interest::Sometimes),
+        ("backend=debug,backend::api=info", Interest::Never, Interest::Sometimes),
+        (
+            "backend=info,backend::api[user=alice]=debug",
+            Interest::Sometimes,
+            Interest::Sometimes,
+        ),
+        (
+            "backend::api[user=alice]=debug,backend::api::handle=info",
+            Interest::Never,
+            Interest::Sometimes,
+        ),
+        ("info,**::handle=debug", Interest::Always, Interest::Sometimes),
+    ];
+
+    for (filter, span_interest, event_interest) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(parsed.callsite_interest(&span), span_interest, "span with {filter}");
+        assert_eq!(parsed.callsite_interest(&event), event_interest, "event with {filter}");
+    }
+}
+
+/// Value which counts how many times it has been formatted.
+struct Counted(Arc<AtomicUsize>);
+
+impl Display for Counted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        write!(f, "counted")
+    }
+}
+
+fn emit(formatted: &Arc<AtomicUsize>) {
+    info!("hot path", value = Counted(formatted.clone()));
+}
+
+#[traced(level = Info, skip_all, ret(Display))]
+fn traced_return(formatted: &Arc<AtomicUsize>) -> Counted {
+    Counted(formatted.clone())
+}
+
+#[test]
+fn disabled_callsites_are_skipped_until_reloaded() {
+    let sink = RecordingSink::default();
+    let formatted = Arc::new(AtomicUsize::new(0));
+
+    set_sink(sink.clone());
+    set_filter(parse("warn").unwrap());
+
+    for _ in 0..100 {
+        emit(&formatted);
+        traced_return(&formatted);
+    }
+
+    assert_eq!(sink.events().len(), 0);
+    assert_eq!(formatted.load(Ordering::Relaxed), 0);
+
+    // Changing the filter must invalidate the cached interest.
+    set_filter(parse("warn,callsite=info").unwrap());
+    emit(&formatted);
+
+    assert_eq!(sink.events().len(), 1);
+    assert_eq!(formatted.load(Ordering::Relaxed), 1);
+
+    set_filter(parse("warn").unwrap());
+    emit(&formatted);
+
+    assert_eq!(sink.events().len(), 1);
+}
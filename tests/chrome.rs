@@ -0,0 +1,125 @@
+use libftrace::{ChromeSink, Context, EventMetadata, Level, Sink, SpanMetadata};
+use serde_json::{Map, Value as Json};
+
+/// Parses the output of a [`ChromeSink`], closing the array which the sink
+/// leaves open.
+fn trace_events(output: &[u8]) -> Vec<Map<String, Json>> {
+    let output = format!("{}]", std::str::from_utf8(output).unwrap());
+
+    serde_json::from_str(&output).unwrap_or_else(|err| panic!("{output}: {err}"))
+}
+
+#[test]
+fn spans_and_events_are_written_as_trace_events() {
+    let span = SpanMetadata::new("load \"config\"", Level::Debug).with_field("retries", 3u64);
+    let event = EventMetadata::new(String::from("slow\nread"), Level::Warn)
+        .with_field("path", "C:\\config.toml")
+        .with_field("ratio", f64::NAN);
+
+    let cx = |thread_id| Context {
+        depth: 0,
+        parent: None,
+        thread_id,
+    };
+
+    let mut output = Vec::new();
+
+    {
+        let sink = ChromeSink::new(&mut output);
+
+        sink.enter_span(&span, &cx(1)).unwrap();
+        sink.event(&event, &cx(1)).unwrap();
+        sink.exit_span(&span, &cx(1)).unwrap();
+        sink.event(&event, &cx(2)).unwrap();
+    }
+
+    assert!(output.starts_with(b"[\n"));
+    assert!(!output.ends_with(b"]"));
+
+    let events = trace_events(&output);
+    let pid = std::process::id();
+    let thread_name = std::thread::current().name().unwrap().to_owned();
+
+    // Each thread is named once, before its first trace event.
+    let phases = events
+        .iter()
+        .map(|event| event["ph"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(phases, vec!["M", "i", "X", "M", "i"]);
+
+    for (event, tid) in [(&events[0], 1), (&events[3], 2)] {
+        assert_eq!(event["name"], "thread_name");
+        assert_eq!(event["tid"], tid);
+        assert_eq!(event["args"]["name"], thread_name);
+    }
+
+    let instant = &events[1];
+    assert_eq!(instant["name"], "slow\nread");
+    assert_eq!(instant["cat"], "WARN");
+    assert_eq!(instant["s"], "t");
+    assert_eq!(instant["pid"], pid);
+    assert_eq!(instant["tid"], 1);
+    assert_eq!(instant["args"]["path"], "C:\\config.toml");
+    assert_eq!(instant["args"]["ratio"], Json::Null);
+    assert!(!instant.contains_key("dur"));
+
+    let complete = &events[2];
+    assert_eq!(complete["name"], "load \"config\"");
+    assert_eq!(complete["cat"], "DEBUG");
+    assert_eq!(complete["tid"], 1);
+    assert_eq!(complete["args"]["retries"], 3);
+    assert!(complete["dur"].as_f64().unwrap() >= 0.0);
+
+    // Timestamps are relative to the creation of the sink.
+    let timestamps = [&events[1], &events[2], &events[4]].map(|event| event["ts"].as_f64().unwrap());
+    assert!(timestamps.iter().all(|&ts| ts >= 0.0), "{timestamps:?}");
+    assert_eq!(events[4]["tid"], 2);
+}
+
+/// Writer which appends to a buffer shared with the test.
+#[cfg(feature = "enabled")]
+#[derive(Clone, Default)]
+struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(feature = "enabled")]
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+#[cfg(feature = "enabled")]
+fn threads_are_not_named_by_spans_closed_elsewhere() {
+    let output = SharedBuffer::default();
+
+    libftrace::set_sink(ChromeSink::new(output.clone()));
+    libftrace::set_filter(libftrace::filter::parse("trace").unwrap());
+
+    let span = std::thread::Builder::new()
+        .name(String::from("entering"))
+        .spawn(|| libftrace::span!(Level::Info, "moved"))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    std::thread::Builder::new()
+        .name(String::from("closing"))
+        .spawn(move || drop(span))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let events = trace_events(&output.0.lock().unwrap());
+    let phases = events
+        .iter()
+        .map(|event| event["ph"].as_str().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(phases, vec!["X"]);
+    assert_eq!(events[0]["name"], "moved");
+}
@@ -0,0 +1,123 @@
+//! Sink which is shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use libftrace::*;
+
+/// Span which was entered or exited, as seen by a [`RecordingSink`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanRecord {
+    pub name: &'static str,
+    pub target: &'static str,
+    pub level: Level,
+    pub fields: Vec<(&'static str, Value)>,
+
+    /// Return value of the span, which is only recorded once it's exited.
+    pub return_value: Option<String>,
+
+    /// Name of the span which the span was entered within, if any.
+    pub parent: Option<&'static str>,
+    pub depth: usize,
+}
+
+/// Event which was emitted, as seen by a [`RecordingSink`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventRecord {
+    pub message: String,
+    pub target: &'static str,
+    pub level: Level,
+    pub fields: Vec<(&'static str, Value)>,
+
+    /// Name of the span which the event was emitted within, if any.
+    pub parent: Option<&'static str>,
+    pub depth: usize,
+}
+
+/// Records every span and event it receives, so tests can assert on them.
+///
+/// Clones share the same records, so one clone can be installed with
+/// [`set_sink`] while the test keeps the other.
+#[derive(Default, Clone)]
+pub struct RecordingSink {
+    entered: Arc<Mutex<Vec<SpanRecord>>>,
+    exited: Arc<Mutex<Vec<SpanRecord>>>,
+    events: Arc<Mutex<Vec<EventRecord>>>,
+}
+
+impl RecordingSink {
+    /// Returns all spans which have been entered, in order.
+    pub fn entered(&self) -> MutexGuard<'_, Vec<SpanRecord>> {
+        self.entered.lock().unwrap()
+    }
+
+    /// Returns all spans which have been exited, in order.
+    pub fn exited(&self) -> MutexGuard<'_, Vec<SpanRecord>> {
+        self.exited.lock().unwrap()
+    }
+
+    /// Returns all events which have been emitted, in order.
+    pub fn events(&self) -> MutexGuard<'_, Vec<EventRecord>> {
+        self.events.lock().unwrap()
+    }
+
+    /// Maps every span which has been entered, in order.
+    pub fn map_entered<T>(&self, f: impl FnMut(&SpanRecord) -> T) -> Vec<T> {
+        self.entered().iter().map(f).collect()
+    }
+
+    /// Maps every span which has been exited, in order.
+    pub fn map_exited<T>(&self, f: impl FnMut(&SpanRecord) -> T) -> Vec<T> {
+        self.exited().iter().map(f).collect()
+    }
+
+    /// Maps every event which has been emitted, in order.
+    pub fn map_events<T>(&self, f: impl FnMut(&EventRecord) -> T) -> Vec<T> {
+        self.events().iter().map(f).collect()
+    }
+
+    /// Forgets all spans and events which have been recorded so far.
+    pub fn clear(&self) {
+        self.entered().clear();
+        self.exited().clear();
+        self.events().clear();
+    }
+}
+
+fn span_record(span: &SpanMetadata, cx: &Context<'_>) -> SpanRecord {
+    SpanRecord {
+        name: span.name,
+        target: span.target,
+        level: span.level,
+        fields: span.fields().map(|(key, value)| (key, value.clone())).collect(),
+        return_value: span.return_value().map(|value| value.to_string()),
+        parent: cx.parent.map(|parent| parent.name),
+        depth: cx.depth,
+    }
+}
+
+impl Sink for RecordingSink {
+    fn enter_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.entered().push(span_record(span, cx));
+        Ok(())
+    }
+
+    fn exit_span(&self, span: &SpanMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.exited().push(span_record(span, cx));
+        Ok(())
+    }
+
+    fn event(&self, event: &EventMetadata, cx: &Context<'_>) -> std::io::Result<()> {
+        self.events().push(EventRecord {
+            message: event.message.clone(),
+            target: event.target,
+            level: event.level,
+            fields: event.fields().map(|(key, value)| (key, value.clone())).collect(),
+            parent: cx.parent.map(|parent| parent.name),
+            depth: cx.depth,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(not(feature = "enabled"))]
+
+use std::fmt::Debug;
+
+use libftrace::*;
+
+/// Value whose fields must never be evaluated while tracing is disabled.
+fn unreachable_value() -> u64 {
+    panic!("fields of disabled spans and events must never be evaluated")
+}
+
+#[derive(Debug)]
+struct Request {
+    method: &'static str,
+}
+
+#[traced(level = Debug, fields(method = req.method, id = unreachable_value()))]
+fn handle(req: Request, id: u64) -> u64 {
+    id
+}
+
+#[traced(skip_all, err, ret)]
+fn lookup(key: &str) -> Result<u32, String> {
+    Err(format!("{key} not found"))
+}
+
+struct Parser<T> {
+    input: Vec<T>,
+}
+
+#[traced(level = Debug)]
+impl<T: Clone + Debug> Parser<T> {
+    fn parse(&self) -> Vec<T> {
+        self.input.clone()
+    }
+}
+
+#[test]
+fn disabled_macros_are_type_checked_but_not_evaluated() {
+    set_filter(filter::parse("trace").unwrap());
+
+    event!(level: Level::Info, "request", id = unreachable_value());
+    event!(level: Level::Info, target: "http", "request {}", unreachable_value(), id = 1);
+    info!("formatted {}", unreachable_value());
+    debug!("plain message");
+
+    let span = span!(target: "db", Level::Info, "query", id = unreachable_value());
+    assert!(span.is_disabled());
+    span.in_scope(|| trace!("inside span", id = unreachable_value()));
+
+    assert_eq!(handle(Request { method: "GET" }, 1), 1);
+    assert_eq!(lookup("user"), Err(String::from("user not found")));
+    assert_eq!(Parser { input: vec![1, 2] }.parse(), vec![1, 2]);
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Writer which is shared between a [`PrettySink`] and the test, so the
+/// output can be inspected after the sink has been installed.
+#[derive(Default, Clone)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Takes all output which has been written so far.
+    fn take(&self) -> String {
+        String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[traced(ret)]
+fn answer() -> u32 {
+    42
+}
+
+#[traced]
+fn nothing() {}
+
+#[test]
+fn exit_records_contain_elapsed_time_and_return_value() {
+    let output = SharedBuffer::default();
+    let sink = RecordingSink::default();
+
+    set_sink(PrettySink::new(output.clone()));
+    add_sink(sink.clone());
+
+    assert_eq!(answer(), 42);
+    nothing();
+
+    let text = output.take();
+    let lines = text.lines().collect::<Vec<_>>();
+    let exits = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.contains("exited after"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(exits.len(), 2, "{text}");
+
+    // The elapsed time is written as a duration, such as `1.2µs`.
+    for (_, line) in &exits {
+        let elapsed = line.rsplit(' ').next().unwrap();
+
+        assert!(elapsed.starts_with(|c: char| c.is_ascii_digit()), "{line}");
+        assert!(elapsed.ends_with('s'), "{line}");
+    }
+
+    // Only spans which recorded a return value show it, below the exit line.
+    let (idx, line) = exits[0];
+    assert!(line.contains("exits::answer"), "{line}");
+    assert!(
+        lines[idx + 1].contains("returned") && lines[idx + 1].contains("42"),
+        "{text}"
+    );
+
+    assert!(exits[1].1.contains("exits::nothing"), "{text}");
+    assert_eq!(text.matches("returned").count(), 1, "{text}");
+
+    // Return values are also given to other sinks, but only when exiting.
+    let returns = sink.map_exited(|span| (span.name, span.return_value.clone()));
+    assert_eq!(returns, vec![
+        ("exits::answer", Some(String::from("42"))),
+        ("exits::nothing", None),
+    ]);
+    assert!(sink.entered().iter().all(|span| span.return_value.is_none()));
+
+    // Exit records can be disabled, while still writing the span itself.
+    set_sink(PrettySink::new(output.clone()).with_span_exits(false));
+    assert_eq!(answer(), 42);
+
+    let text = output.take();
+    assert!(text.contains("exits::answer"), "{text}");
+    assert!(!text.contains("exited after"), "{text}");
+    assert!(!text.contains("returned"), "{text}");
+}
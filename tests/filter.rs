@@ -0,0 +1,470 @@
+use libftrace::filter::{ParseErrorKind, parse};
+use libftrace::{EventMetadata, Level, SpanMetadata, Value};
+
+#[test]
+fn malformed_directives_return_errors() {
+    let cases: &[(&str, ParseErrorKind, usize, &str)] = &[
+        ("backend=", ParseErrorKind::MissingLevel, 8, "backend="),
+        (
+            "info,db=verbose",
+            ParseErrorKind::InvalidLevel(String::from("verbose")),
+            8,
+            "db=verbose",
+        ),
+        (
+            "backend,info",
+            ParseErrorKind::InvalidLevel(String::from("backend")),
+            0,
+            "backend",
+        ),
+        ("info,,debug", ParseErrorKind::EmptyDirective, 5, ""),
+        ("db[=1]=info", ParseErrorKind::MissingFieldKey, 3, "db[=1]=info"),
+        ("db[1id=1]=info", ParseErrorKind::MissingFieldKey, 3, "db[1id=1]=info"),
+        ("db[id?1]=info", ParseErrorKind::InvalidFieldMode, 5, "db[id?1]=info"),
+        ("db[id~1]=info", ParseErrorKind::InvalidFieldMode, 5, "db[id~1]=info"),
+        ("x[a~^=b]=info", ParseErrorKind::InvalidFieldMode, 3, "x[a~^=b]=info"),
+        ("x[a!~=b]=info", ParseErrorKind::InvalidFieldMode, 3, "x[a!~=b]=info"),
+        ("db[id=]=info", ParseErrorKind::MissingFieldValue, 6, "db[id=]=info"),
+        (
+            "db[id=\"1]=info",
+            ParseErrorKind::UnterminatedString,
+            6,
+            "db[id=\"1]=info",
+        ),
+        ("db[id=1=info", ParseErrorKind::UnclosedFieldList, 7, "db[id=1=info"),
+        (
+            "warn,db=info error",
+            ParseErrorKind::UnexpectedCharacter(' '),
+            12,
+            "db=info error",
+        ),
+        (
+            "info,aé=debug",
+            ParseErrorKind::UnexpectedCharacter('é'),
+            6,
+            "aé=debug",
+        ),
+        ("info,-é", ParseErrorKind::UnexpectedCharacter('é'), 6, "-é"),
+        ("info,-db=warn", ParseErrorKind::UnexpectedLevel, 8, "-db=warn"),
+        ("info,-", ParseErrorKind::EmptyDirective, 6, "-"),
+        (
+            "app::*x::handler=debug",
+            ParseErrorKind::InvalidTargetSegment(String::from("*x")),
+            5,
+            "app::*x::handler=debug",
+        ),
+        (
+            "app::::handler=debug",
+            ParseErrorKind::InvalidTargetSegment(String::new()),
+            5,
+            "app::::handler=debug",
+        ),
+        (
+            "info,-app:handler",
+            ParseErrorKind::InvalidTargetSegment(String::from("app:handler")),
+            6,
+            "-app:handler",
+        ),
+    ];
+
+    for (filter, kind, offset, directive) in cases {
+        let err = parse(filter).expect_err(filter);
+
+        assert_eq!(&err.kind, kind, "{filter}");
+        assert_eq!(err.offset, *offset, "{filter}");
+        assert_eq!(err.directive, *directive, "{filter}");
+    }
+}
+
+#[test]
+fn well_formed_directives_are_parsed() {
+    let filters = [
+        "",
+        "info",
+        "backend=info,debug",
+        "backend::db=trace,warn",
+        "backend[id=1]",
+        "backend[name~=\"John Doe\",id^=12]=debug",
+        "off",
+        "backend=trace,-backend::db,backend::cache=off",
+        "-backend[user=healthcheck]",
+    ];
+
+    for filter in filters {
+        if let Err(err) = parse(filter) {
+            panic!("{filter}: {}", err.render());
+        }
+    }
+}
+
+#[test]
+fn glob_and_regex_field_filters() {
+    let span = |path: &str, id: u64| {
+        SpanMetadata::new("backend::api", Level::Info)
+            .with_field("path", path)
+            .with_field("id", id)
+    };
+
+    let cases = [
+        ("backend[path*=\"/api/*/orders\"]=info", "/api/v2/orders", 1, true),
+        ("backend[path*=\"/api/*/orders\"]=info", "/api/v2/users", 1, false),
+        ("backend[path*=\"/api/v?/*\"]=info", "/api/v2/orders", 1, true),
+        ("backend[path*=\"/api/v?/*\"]=info", "/api/v10/orders", 1, false),
+        ("backend[path*=*orders]=info", "/api/v2/orders", 1, true),
+        ("backend[path*=\"*/v?\"]=info", "/api/v2/orders/v3", 1, true),
+        ("backend[path*=\"*/*/*\"]=info", "/api", 1, false),
+        // Patterns with many stars must not backtrack exponentially.
+        (
+            "backend[path*=*a*a*a*a*a*a*a*a*a*a*a*a*b]=info",
+            "/aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            1,
+            false,
+        ),
+        (
+            "backend[path*=*a*a*a*a*a*a*a*a*a*a*a*a*b]=info",
+            "/aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab",
+            1,
+            true,
+        ),
+        #[cfg(feature = "regex")]
+        ("backend[id=/^(12|13|99)$/]=info", "/", 13, true),
+        #[cfg(feature = "regex")]
+        ("backend[id=/^(12|13|99)$/]=info", "/", 130, false),
+        #[cfg(feature = "regex")]
+        ("backend[path=/^\\/api\\/v\\d+\\//]=info", "/api/v2/orders", 1, true),
+        #[cfg(feature = "regex")]
+        (
+            "backend[path=/^\\/api\\/v\\d+\\//]=info",
+            "/api/latest/orders",
+            1,
+            false,
+        ),
+    ];
+
+    for (filter, path, id, expected) in cases {
+        let filter = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            filter.span_enabled(&span(path, id)),
+            expected,
+            "{filter:?} on {path}, {id}"
+        );
+    }
+}
+
+#[test]
+fn malformed_patterns_return_errors() {
+    let cases = [
+        #[cfg(feature = "regex")]
+        ("backend[id=/^(12|13/]=info", "invalid regular expression"),
+        #[cfg(not(feature = "regex"))]
+        ("backend[id=/^12/]=info", "the `regex` feature is not enabled"),
+        ("backend[id=/^12]=info", "unterminated regular expression"),
+        (
+            "backend[id~=/^12/]=info",
+            "regular expressions can only be matched using `=`",
+        ),
+        #[cfg(feature = "regex")]
+        ("a[id=/]/,k~x]=info", "after field name"),
+    ];
+
+    for (filter, message) in cases {
+        let err = parse(filter).expect_err(filter);
+
+        assert!(err.to_string().contains(message), "{filter}: {err}");
+        assert!(err.render().contains(filter), "{filter}: {}", err.render());
+    }
+}
+
+#[test]
+fn numeric_field_filters() {
+    let span = |status: Value| {
+        SpanMetadata::new("backend::handle_request", Level::Info)
+            .with_field("status", status.clone())
+            .with_field("latency_ms", status.clone())
+            .with_field("user_id", status)
+    };
+
+    let cases = [
+        ("backend[status>=500]=info", Value::U64(503), true),
+        ("backend[status>=500]=info", Value::U64(500), true),
+        ("backend[status>=500]=info", Value::U64(60), false),
+        ("backend[status>500]=info", Value::U64(500), false),
+        ("backend[status<500]=info", Value::I64(-1), true),
+        ("backend[status<=1.5]=info", Value::F64(1.5), true),
+        ("backend[status<1.5]=info", Value::I64(1), true),
+        ("backend[status>100]=info", Value::Str("404".into()), true),
+        ("backend[status>100]=info", Value::Str("abc".into()), false),
+        ("backend[status>abc]=info", Value::Str("abd".into()), false),
+        ("backend[status!=500]=info", Value::U64(500), false),
+        ("backend[status!=500]=info", Value::F64(500.0), false),
+        ("backend[status!=500]=info", Value::U64(404), true),
+        ("backend[status!=ok]=info", Value::Str("ok".into()), false),
+        ("backend[status!=ok]=info", Value::Str("failed".into()), true),
+        ("backend[status=1.0]=info", Value::F64(1.0), true),
+        ("backend[status!=1.0]=info", Value::F64(1.0), false),
+        ("backend[status=01]=info", Value::I64(1), true),
+        ("backend[status!=01]=info", Value::I64(1), false),
+        ("backend[status=500]=info", Value::U64(404), false),
+        ("backend[latency_ms>200]=info", Value::U64(350), true),
+        ("backend[latency_ms>200]=info", Value::U64(150), false),
+        ("backend[user_id=3]=info", Value::U64(3), true),
+        ("backend[user_id=3]=info", Value::U64(4), false),
+        ("backend[latency_ms<=350]=info", Value::U64(350), true),
+    ];
+
+    for (filter, status, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.span_enabled(&span(status.clone())),
+            expected,
+            "{filter} on {status:?}"
+        );
+    }
+}
+
+#[test]
+fn exclusions_take_precedence() {
+    let span = |name: &'static str, level: Level, user: &str| SpanMetadata::new(name, level).with_field("user", user);
+
+    let cases = [
+        ("backend=trace,-backend::db", "backend::api", Level::Trace, true),
+        ("backend=trace,-backend::db", "backend::db", Level::Error, false),
+        ("-backend::db,backend=trace", "backend::db", Level::Error, false),
+        ("backend=trace,backend::db=off", "backend::db", Level::Error, false),
+        ("info,-backend::db", "backend::db", Level::Error, false),
+        ("info,-backend::db", "frontend", Level::Info, true),
+        ("off", "backend::api", Level::Error, false),
+        ("off,backend=info", "backend::api", Level::Info, true),
+        (
+            "backend=info,-backend[user=healthcheck]",
+            "backend::api",
+            Level::Info,
+            false,
+        ),
+        (
+            "backend=info,-backend[user=healthcheck]",
+            "backend::db",
+            Level::Warn,
+            false,
+        ),
+        ("backend[user!=healthcheck]=info", "backend::api", Level::Info, false),
+    ];
+
+    for (filter, name, level, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.span_enabled(&span(name, level, "healthcheck")),
+            expected,
+            "{filter} on {name} at {level:?}"
+        );
+    }
+
+    let parsed = parse("backend=info,-backend[user=healthcheck]").unwrap();
+    assert!(parsed.span_enabled(&span("backend::api", Level::Info, "alice")));
+}
+
+#[test]
+fn most_specific_directive_wins() {
+    let span = |name: &'static str, level: Level| SpanMetadata::new(name, level).with_field("user", "alice");
+
+    let cases = [
+        // Longer targets take precedence over shorter targets.
+        ("backend=error,backend::api=trace", "backend::api", Level::Trace, true),
+        ("backend=trace,backend::api=error", "backend::api", Level::Warn, false),
+        ("backend::api=error,backend=trace", "backend::api", Level::Warn, false),
+        ("backend=trace,backend::api=error", "backend::db", Level::Trace, true),
+        ("backend=error,backend::api=trace", "backend::db", Level::Warn, false),
+        // Targets take precedence over the default level.
+        ("trace,backend=error", "backend::api", Level::Warn, false),
+        ("error,backend=trace", "backend::api", Level::Trace, true),
+        ("error,backend=trace", "frontend", Level::Warn, false),
+        // More field filters take precedence over fewer field filters.
+        (
+            "backend[user=alice]=trace,backend=error",
+            "backend::api",
+            Level::Trace,
+            true,
+        ),
+        (
+            "backend=trace,backend[user=alice]=error",
+            "backend::api",
+            Level::Trace,
+            false,
+        ),
+        (
+            "backend[user=bob]=trace,backend=error",
+            "backend::api",
+            Level::Trace,
+            false,
+        ),
+        // Longer targets take precedence over more field filters.
+        (
+            "backend[user=alice]=error,backend::api=trace",
+            "backend::api",
+            Level::Trace,
+            true,
+        ),
+        // Exclusions follow the same rules, but win over equal directives.
+        ("-backend,backend::api=trace", "backend::api", Level::Trace, true),
+        ("-backend,backend::api=trace", "backend::db", Level::Error, false),
+        ("backend=trace,-backend", "backend::api", Level::Error, false),
+        ("-backend,backend=trace", "backend::api", Level::Error, false),
+        (
+            "backend::api=trace,-backend[user=alice]",
+            "backend::api",
+            Level::Error,
+            true,
+        ),
+        // Otherwise, the last directive wins.
+        ("backend=error,backend=trace", "backend::api", Level::Trace, true),
+        ("backend=trace,backend=error", "backend::api", Level::Trace, false),
+    ];
+
+    for (filter, name, level, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.span_enabled(&span(name, level)),
+            expected,
+            "{filter} on {name} at {level:?}"
+        );
+    }
+}
+
+#[test]
+fn most_specific_directive_wins_for_events() {
+    let parent = SpanMetadata::new("backend::api", Level::Info);
+    let event = |level: Level| EventMetadata::new("request handled", level);
+
+    let cases = [
+        ("backend=error,backend::api=trace", Level::Debug, true),
+        ("backend=trace,backend::api=error", Level::Debug, false),
+        ("backend=trace,-backend::api", Level::Error, false),
+        ("-backend,backend::api=debug", Level::Debug, true),
+        ("info,backend::api=debug", Level::Debug, true),
+    ];
+
+    for (filter, level, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.event_enabled(&event(level), Some(&parent)),
+            expected,
+            "{filter} at {level:?}"
+        );
+    }
+}
+
+#[test]
+fn targets_match_segments_and_wildcards() {
+    let cases = [
+        ("backend=info", "backend", true),
+        ("backend=info", "backend::api", true),
+        ("backend=info", "backend_legacy::api", false),
+        ("backend::api=info", "backend::api_v2", false),
+        ("backend::api=info", "backend", false),
+        ("app::*::handler=info", "app::users::handler", true),
+        ("app::*::handler=info", "app::orders::handler::inner", true),
+        ("app::*::handler=info", "app::handler", false),
+        ("app::*::handler=info", "app::users::admin::handler", false),
+        ("**::parse=info", "parse", true),
+        ("**::parse=info", "config::toml::parse", true),
+        ("**::parse=info", "config::toml::parser", false),
+        ("app::**::handler=info", "app::handler", true),
+        ("app::**::handler=info", "app::users::admin::handler", true),
+        ("app::**=info", "app", true),
+        ("*=info", "anything::at::all", true),
+    ];
+
+    for (filter, name, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.span_enabled(&SpanMetadata::new(name, Level::Info)),
+            expected,
+            "{filter} on {name}"
+        );
+    }
+}
+
+#[test]
+fn named_segments_are_more_specific_than_wildcards() {
+    let cases = [
+        ("app::*::handler=error,app::users::handler=trace", true),
+        ("app::users::handler=trace,app::*::handler=error", true),
+        ("app::**=trace,app::users=error", false),
+        ("app::*=error,app::**::handler=trace", true),
+        ("**::handler=trace,app=error", false),
+    ];
+
+    for (filter, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.span_enabled(&SpanMetadata::new("app::users::handler", Level::Debug)),
+            expected,
+            "{filter}"
+        );
+    }
+}
+
+#[test]
+fn events_are_matched_by_their_target() {
+    let event = |target: &'static str, level: Level| EventMetadata::new("connected", level).with_target(target);
+    let parent = SpanMetadata::new("backend::api", Level::Info);
+
+    let cases = [
+        ("backend::db=debug", "backend::db::pool", None, true),
+        ("backend::db=debug", "backend::cache", None, false),
+        ("warn,backend::db=debug", "frontend", None, false),
+        ("backend::db=debug", "backend::cache", Some(&parent), false),
+        ("backend::api=debug", "backend::cache", Some(&parent), true),
+        ("backend=debug,-backend::db", "backend::db", Some(&parent), false),
+        ("backend::api=debug,-backend::db", "backend::db", Some(&parent), false),
+        ("backend=error,backend::db=debug", "backend::db", Some(&parent), true),
+    ];
+
+    for (filter, target, parent, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+
+        assert_eq!(
+            parsed.event_enabled(&event(target, Level::Debug), parent),
+            expected,
+            "{filter} on {target}"
+        );
+    }
+}
+
+#[test]
+fn events_are_matched_by_any_ancestor() {
+    let request = |user: &str| SpanMetadata::new("app::handle_request", Level::Info).with_field("user", user);
+    let helper = SpanMetadata::new("db::query", Level::Info);
+    let event = EventMetadata::new("row fetched", Level::Trace)
+        .with_target("db::pool")
+        .with_field("rows", 1u32);
+
+    let cases = [
+        ("app::handle_request[user=alice]=trace", "alice", true),
+        ("app::handle_request[user=alice]=trace", "bob", false),
+        ("app::handle_request[user=alice,rows=1]=trace", "alice", true),
+        ("app::handle_request[user=alice,rows=2]=trace", "alice", false),
+        ("app=trace", "bob", true),
+        ("app=trace,-db::query", "bob", false),
+        ("app=trace,db=info", "bob", false),
+        ("info,db::pool[rows=1]=trace", "bob", true),
+    ];
+
+    for (filter, user, expected) in cases {
+        let parsed = parse(filter).unwrap_or_else(|err| panic!("{filter}: {}", err.render()));
+        let request = request(user);
+
+        assert_eq!(
+            parsed.event_enabled(&event, [&helper, &request]),
+            expected,
+            "{filter} for {user}"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::fmt::{Debug, Display};
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Returns the name and level of every span which has been entered.
+fn spans(sink: &RecordingSink) -> Vec<(&'static str, Level)> {
+    sink.entered().iter().map(|span| (span.name, span.level)).collect()
+}
+
+struct Parser<T> {
+    input: Vec<T>,
+}
+
+#[traced(level = Warn)]
+impl<T: Clone + Debug> Parser<T> {
+    fn parse(&self) -> Vec<T> {
+        self.input.iter().map(|token| self.next_token(token)).collect()
+    }
+
+    #[traced(level = Info)]
+    fn next_token(&self, token: &T) -> T {
+        token.clone()
+    }
+
+    // Arguments of methods are merged with the arguments of the block.
+    #[traced(ret)]
+    fn count(&self) -> usize {
+        self.input.len()
+    }
+
+    #[traced(skip)]
+    fn len(&self) -> usize {
+        self.input.len()
+    }
+
+    // Constant functions can't be traced, so they're skipped.
+    const fn is_parser() -> bool {
+        true
+    }
+}
+
+struct Token;
+
+#[traced(skip_all)]
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "token")
+    }
+}
+
+// Paths of the `Self` type aren't repeated in the span names.
+#[traced(skip_all)]
+impl crate::Token {
+    fn kind(&self) -> &'static str {
+        "token"
+    }
+}
+
+trait Describe {
+    fn describe(&self) -> &'static str;
+}
+
+// Types without a path are named after the types they refer to.
+#[traced]
+impl Describe for &Token {
+    fn describe(&self) -> &'static str {
+        "reference"
+    }
+}
+
+#[traced]
+impl Describe for [Token] {
+    fn describe(&self) -> &'static str {
+        "slice"
+    }
+}
+
+#[traced]
+impl Describe for (Token, Token) {
+    fn describe(&self) -> &'static str {
+        "tuple"
+    }
+}
+
+struct Id(u64);
+
+// Implementations of the same generic trait are told apart by their arguments.
+#[traced]
+impl From<u32> for Id {
+    fn from(value: u32) -> Self {
+        Id(u64::from(value))
+    }
+}
+
+#[traced]
+impl From<&str> for Id {
+    fn from(value: &str) -> Self {
+        Id(value.len() as u64)
+    }
+}
+
+struct Lexer;
+
+// Arguments skipped by the block don't have to exist on every method.
+#[traced(skip(source))]
+impl Lexer {
+    fn lex(&self, source: &str, offset: usize) -> usize {
+        source.len() - offset
+    }
+
+    fn reset(&self) {}
+}
+
+#[test]
+fn methods_are_named_after_self_type_and_trait() {
+    let sink = RecordingSink::default();
+
+    set_sink(sink.clone());
+    set_filter(filter::parse("trace").unwrap());
+
+    let parser = Parser { input: vec![1, 2] };
+    assert_eq!(parser.parse(), vec![1, 2]);
+    assert_eq!(parser.len(), 2);
+    assert_eq!(parser.count(), 2);
+    assert_eq!(Token.to_string(), "token");
+    assert_eq!(Token.kind(), "token");
+    assert!(const { Parser::<u8>::is_parser() });
+
+    assert_eq!(spans(&sink), vec![
+        ("impl_blocks::Parser::parse", Level::Warn),
+        ("impl_blocks::Parser::next_token", Level::Info),
+        ("impl_blocks::Parser::next_token", Level::Info),
+        ("impl_blocks::Parser::count", Level::Warn),
+        ("<impl_blocks::Token as Display>::fmt", Level::Info),
+        ("impl_blocks::Token::kind", Level::Info),
+    ]);
+
+    let events = sink.map_events(|event| (event.message.clone(), event.fields.clone()));
+
+    assert_eq!(events, vec![(
+        String::from("impl_blocks::Parser::count"),
+        vec![("ret", Value::U64(2))]
+    )]);
+
+    // Methods of trait implementations are still targeted at their module.
+    sink.clear();
+    set_filter(filter::parse("off,impl_blocks::Token=info").unwrap());
+
+    assert_eq!(parser.parse(), vec![1, 2]);
+    assert_eq!(Token.to_string(), "token");
+
+    assert_eq!(spans(&sink), vec![(
+        "<impl_blocks::Token as Display>::fmt",
+        Level::Info
+    )]);
+
+    sink.clear();
+    set_filter(filter::parse("off,impl_blocks=info").unwrap());
+
+    assert_eq!(Token.to_string(), "token");
+    assert_eq!(sink.entered().len(), 1);
+
+    // Arguments skipped by the block are only skipped on methods which have
+    // them.
+    sink.clear();
+    set_filter(filter::parse("impl_blocks::Lexer=info").unwrap());
+
+    assert_eq!(Lexer.lex("token", 2), 3);
+    Lexer.reset();
+
+    let spans = sink.map_entered(|span| (span.name, span.fields.clone()));
+
+    assert_eq!(spans, vec![
+        ("impl_blocks::Lexer::lex", vec![("offset", Value::U64(2))]),
+        ("impl_blocks::Lexer::reset", vec![]),
+    ]);
+
+    sink.clear();
+    set_filter(filter::parse("impl_blocks=info").unwrap());
+
+    assert_eq!((&Token).describe(), "reference");
+    assert_eq!([Token][..].describe(), "slice");
+    assert_eq!((Token, Token).describe(), "tuple");
+
+    let spans = sink.map_entered(|span| (span.name, span.target));
+
+    assert_eq!(spans, vec![
+        ("<&impl_blocks::Token as Describe>::describe", "impl_blocks::Token::describe"),
+        ("<[impl_blocks::Token] as Describe>::describe", "impl_blocks::Token::describe"),
+        (
+            "<(impl_blocks::Token, impl_blocks::Token) as Describe>::describe",
+            "impl_blocks::describe"
+        ),
+    ]);
+
+    sink.clear();
+
+    assert_eq!(Id::from(7u32).0, 7);
+    assert_eq!(Id::from("id").0, 2);
+
+    assert_eq!(sink.map_entered(|span| span.name), vec![
+        "<impl_blocks::Id as From<u32>>::from",
+        "<impl_blocks::Id as From<&str>>::from",
+    ]);
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::future::Future;
+use std::pin::{Pin, pin};
+use std::task::{Poll, Waker};
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Future which returns [`Poll::Pending`] once, before completing.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+
+        Poll::Pending
+    }
+}
+
+/// Polls both futures on the current thread, alternating between them, until
+/// both have completed.
+fn join<A: Future, B: Future>(a: A, b: B) -> (A::Output, B::Output) {
+    let mut cx = std::task::Context::from_waker(Waker::noop());
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    let (mut a_out, mut b_out) = (None, None);
+
+    while a_out.is_none() || b_out.is_none() {
+        if a_out.is_none() {
+            if let Poll::Ready(out) = a.as_mut().poll(&mut cx) {
+                a_out = Some(out);
+            }
+        }
+
+        if b_out.is_none() {
+            if let Poll::Ready(out) = b.as_mut().poll(&mut cx) {
+                b_out = Some(out);
+            }
+        }
+
+        // Neither task should ever leak its span onto the thread.
+        assert_eq!(with_subscriber(|s| s.depth()), 0);
+    }
+
+    (a_out.unwrap(), b_out.unwrap())
+}
+
+#[traced(level = Info)]
+async fn first() {
+    info!("first: before");
+    YieldNow(false).await;
+    info!("first: after");
+}
+
+#[traced(level = Info, err, ret)]
+async fn second(fail: bool) -> Result<u32, String> {
+    info!("second: before");
+    YieldNow(false).await;
+    info!("second: after");
+
+    if fail {
+        return Err(String::from("failed"));
+    }
+
+    Ok(42)
+}
+
+#[test]
+fn async_spans_are_send() {
+    fn assert_send<T: Send>(_: T) {}
+
+    assert_send(first());
+    assert_send(second(false));
+}
+
+#[test]
+fn async_spans_are_entered_per_poll() {
+    let sink = RecordingSink::default();
+    set_sink(sink.clone());
+
+    let ((), result) = join(first(), second(false));
+    assert_eq!(result, Ok(42));
+
+    let (_, result) = join(first(), second(true));
+    assert_eq!(result, Err(String::from("failed")));
+
+    let events = sink.map_events(|event| (event.parent, event.message.clone()));
+    let first = Some("instrument::first");
+    let second = Some("instrument::second");
+
+    assert_eq!(events.as_slice(), &[
+        (first, String::from("first: before")),
+        (second, String::from("second: before")),
+        (first, String::from("first: after")),
+        (second, String::from("second: after")),
+        (second, String::from("instrument::second")),
+        (first, String::from("first: before")),
+        (second, String::from("second: before")),
+        (first, String::from("first: after")),
+        (second, String::from("second: after")),
+        (second, String::from("instrument::second")),
+    ]);
+}
@@ -0,0 +1,117 @@
+use libftrace::{Context, EventMetadata, JsonSink, Level, Sink, SpanMetadata};
+use serde_json::{Map, Value as Json};
+
+/// Keys which are contained in every record, regardless of its kind.
+const KEYS: [&str; 10] = [
+    "timestamp",
+    "kind",
+    "level",
+    "target",
+    "fields",
+    "file",
+    "line",
+    "depth",
+    "parent",
+    "thread_id",
+];
+
+/// Parses each line of the output as a single JSON object.
+fn records(output: &[u8]) -> Vec<Map<String, Json>> {
+    std::str::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|err| panic!("{line}: {err}")))
+        .collect()
+}
+
+#[test]
+fn records_are_written_as_json_lines() {
+    let span = SpanMetadata::new("parse \"config\"", Level::Debug)
+        .with_target("config::parse")
+        .with_field("path", "C:\\config\\app.toml");
+
+    let event = EventMetadata::new(String::from("line 1\nline 2\t\"quoted\"\u{7}"), Level::Warn)
+        .with_target("config")
+        .with_field("quote", "say \"hi\"")
+        .with_field("control", "\u{0}\r\u{1b}")
+        .with_field("count", 42u64)
+        .with_field("offset", -3i64)
+        .with_field("ratio", 0.5)
+        .with_field("nan", f64::NAN)
+        .with_field("infinite", f64::INFINITY)
+        .with_field("valid", false);
+
+    let root = Context {
+        depth: 0,
+        parent: None,
+        thread_id: 1,
+    };
+
+    let inner = Context {
+        depth: 1,
+        parent: Some(&span),
+        thread_id: 1,
+    };
+
+    let mut output = Vec::new();
+
+    {
+        let sink = JsonSink::new(&mut output);
+
+        sink.enter_span(&span, &root).unwrap();
+        sink.event(&event, &inner).unwrap();
+        sink.exit_span(&span, &root).unwrap();
+    }
+
+    let records = records(&output);
+    assert_eq!(records.len(), 3);
+
+    for record in &records {
+        for key in KEYS {
+            assert!(record.contains_key(key), "missing {key} in {record:?}");
+        }
+    }
+
+    let [enter, event, exit] = &records[..] else {
+        unreachable!()
+    };
+
+    // Test threads are always named, after the test which they run.
+    let thread_name = std::thread::current().name().unwrap().to_owned();
+    for record in &records {
+        assert_eq!(record["thread_id"], 1);
+        assert_eq!(record["thread_name"], thread_name);
+    }
+
+    assert_eq!(enter["kind"], "enter");
+    assert_eq!(enter["name"], "parse \"config\"");
+    assert_eq!(enter["level"], "DEBUG");
+    assert_eq!(enter["target"], "config::parse");
+    assert_eq!(enter["fields"]["path"], "C:\\config\\app.toml");
+    assert_eq!(enter["depth"], 0);
+    assert_eq!(enter["parent"], Json::Null);
+    assert_eq!(enter["file"], file!());
+    assert!(!enter.contains_key("elapsed_ns"));
+
+    assert_eq!(event["kind"], "event");
+    assert_eq!(event["message"], "line 1\nline 2\t\"quoted\"\u{7}");
+    assert_eq!(event["level"], "WARN");
+    assert_eq!(event["target"], "config");
+    assert_eq!(event["depth"], 1);
+    assert_eq!(event["parent"], "parse \"config\"");
+
+    let fields = &event["fields"];
+    assert_eq!(fields["quote"], "say \"hi\"");
+    assert_eq!(fields["control"], "\u{0}\r\u{1b}");
+    assert_eq!(fields["count"], 42);
+    assert_eq!(fields["offset"], -3);
+    assert_eq!(fields["ratio"], 0.5);
+    assert_eq!(fields["nan"], Json::Null);
+    assert_eq!(fields["infinite"], Json::Null);
+    assert_eq!(fields["valid"], false);
+
+    assert_eq!(exit["kind"], "exit");
+    assert_eq!(exit["name"], "parse \"config\"");
+    assert!(exit["elapsed_ns"].is_u64());
+    assert!(!exit.contains_key("return"));
+}
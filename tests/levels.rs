@@ -0,0 +1,55 @@
+use libftrace::{Level, LevelFilter};
+
+const LEVELS: [Level; 5] = [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+#[test]
+fn level_filters_enable_less_verbose_levels() {
+    let filters = [
+        LevelFilter::Trace,
+        LevelFilter::Debug,
+        LevelFilter::Info,
+        LevelFilter::Warn,
+        LevelFilter::Error,
+    ];
+
+    for (filter_idx, filter) in filters.into_iter().enumerate() {
+        for (level_idx, level) in LEVELS.into_iter().enumerate() {
+            assert_eq!(
+                filter.enables(level),
+                level_idx >= filter_idx,
+                "{filter:?} with {level:?}"
+            );
+        }
+
+        assert_eq!(filter, LevelFilter::from(LEVELS[filter_idx]));
+    }
+
+    for level in LEVELS {
+        assert!(!LevelFilter::Off.enables(level), "off with {level:?}");
+    }
+
+    // Filters can be evaluated in constant contexts.
+    const { assert!(LevelFilter::Warn.enables(Level::Error)) };
+}
+
+#[test]
+#[cfg(not(any(
+    feature = "max_level_off",
+    feature = "max_level_error",
+    feature = "max_level_warn",
+    feature = "max_level_info",
+    feature = "max_level_debug",
+    feature = "release_max_level_off",
+    feature = "release_max_level_error",
+    feature = "release_max_level_warn",
+    feature = "release_max_level_info",
+    feature = "release_max_level_debug",
+)))]
+fn static_max_level_defaults_to_trace() {
+    assert_eq!(libftrace::STATIC_MAX_LEVEL, LevelFilter::Trace);
+    assert!(
+        LEVELS
+            .into_iter()
+            .all(|level| libftrace::STATIC_MAX_LEVEL.enables(level))
+    );
+}
@@ -0,0 +1,48 @@
+//! Run with `cargo test --test max_level --features max_level_info`.
+
+#![cfg(all(
+    feature = "enabled",
+    feature = "max_level_info",
+    debug_assertions,
+    not(any(feature = "max_level_off", feature = "max_level_error", feature = "max_level_warn")),
+))]
+
+mod common;
+
+use common::RecordingSink;
+use libftrace::*;
+
+#[traced(level = Debug)]
+fn verbose() {
+    info!("inside verbose span");
+}
+
+#[traced(level = Info)]
+fn quiet() {
+    debug!("removed event");
+}
+
+#[test]
+fn static_max_level_removes_verbose_items() {
+    assert_eq!(STATIC_MAX_LEVEL, LevelFilter::Info);
+
+    let sink = RecordingSink::default();
+    set_sink(sink.clone());
+    set_filter(filter::parse("trace").unwrap());
+
+    verbose();
+    quiet();
+
+    // Removed spans are never entered, even if the filter would enable them.
+    assert!(span!(Level::Debug, "removed").is_disabled());
+
+    let spans = sink.entered().iter().map(|span| span.name).collect::<Vec<_>>();
+    let events = sink
+        .events()
+        .iter()
+        .map(|event| event.message.clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(spans, vec!["max_level::quiet"]);
+    assert_eq!(events, vec!["inside verbose span"]);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Returns the name and target of every span which has been entered.
+fn spans(sink: &RecordingSink) -> Vec<(&'static str, &'static str)> {
+    sink.entered().iter().map(|span| (span.name, span.target)).collect()
+}
+
+#[traced]
+fn default_name() {}
+
+#[traced(name = "load {user}", err)]
+fn renamed() -> Result<(), &'static str> {
+    Err("not found")
+}
+
+#[traced(target = "db::query")]
+fn retargeted() {}
+
+#[traced(name = "insert", target = "db::insert")]
+fn both() {}
+
+#[test]
+fn spans_are_filtered_by_target_and_shown_by_name() {
+    let sink = RecordingSink::default();
+
+    set_sink(sink.clone());
+    set_filter(filter::parse("info").unwrap());
+
+    default_name();
+    let _ = renamed();
+    retargeted();
+    both();
+
+    assert_eq!(spans(&sink), vec![
+        ("overrides::default_name", "overrides::default_name"),
+        ("load {user}", "overrides::renamed"),
+        ("overrides::retargeted", "db::query"),
+        ("insert", "db::insert"),
+    ]);
+
+    let messages = sink
+        .events()
+        .iter()
+        .map(|event| event.message.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, vec!["load {user}"]);
+
+    // Filters only match the target, never the name.
+    sink.clear();
+    set_filter(filter::parse("off,db=info,insert=info").unwrap());
+
+    default_name();
+    retargeted();
+    both();
+
+    assert_eq!(spans(&sink), vec![
+        ("overrides::retargeted", "db::query"),
+        ("insert", "db::insert")
+    ]);
+}
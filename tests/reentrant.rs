@@ -0,0 +1,97 @@
+#![cfg(feature = "enabled")]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use libftrace::*;
+
+#[traced(level = Info, skip_all)]
+fn render(message: &str) -> String {
+    message.to_uppercase()
+}
+
+/// Sink which calls a traced function for every event it receives, while
+/// another thread adds a sink to the subscriber.
+#[derive(Clone, Default)]
+struct TracingSink {
+    spans: Arc<AtomicUsize>,
+    events: Arc<AtomicUsize>,
+}
+
+impl Sink for TracingSink {
+    fn enter_span(&self, _span: &SpanMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        self.spans.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn event(&self, event: &EventMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        assert_eq!(render(&event.message), "WITHIN SPAN");
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            add_sink(FailingSink);
+            tx.send(()).unwrap();
+        });
+
+        rx.recv_timeout(Duration::from_secs(10))
+            .expect("sinks must be replaceable while they are running");
+
+        // Once a writer has been waiting, entering spans must still not block.
+        assert_eq!(render(&event.message), "WITHIN SPAN");
+
+        self.events.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Sink which fails to write every event.
+struct FailingSink;
+
+impl Sink for FailingSink {
+    fn enter_span(&self, _span: &SpanMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn event(&self, _event: &EventMetadata, _cx: &Context<'_>) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+#[traced(level = Info)]
+fn outer() {
+    info!("within span");
+}
+
+#[test]
+fn sinks_can_enter_spans_and_be_replaced() {
+    let sink = TracingSink::default();
+
+    set_sink(sink.clone());
+    set_error_handler(ErrorHandler::Ignore);
+    set_filter(filter::parse("info").unwrap());
+
+    outer();
+
+    assert_eq!(sink.events.load(Ordering::Relaxed), 1);
+    assert_eq!(sink.spans.load(Ordering::Relaxed), 3);
+
+    // Custom error handlers may replace the error handler while running.
+    let handled = Arc::new(AtomicUsize::new(0));
+
+    set_sink(FailingSink);
+    set_error_handler(ErrorHandler::Custom(Box::new({
+        let handled = handled.clone();
+
+        move |_| {
+            handled.fetch_add(1, Ordering::Relaxed);
+            set_error_handler(ErrorHandler::Ignore);
+        }
+    })));
+
+    info!("handled");
+    info!("ignored");
+
+    assert_eq!(handled.load(Ordering::Relaxed), 1);
+}
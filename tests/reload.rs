@@ -0,0 +1,93 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::sync::{Arc, Barrier};
+
+use common::RecordingSink;
+use libftrace::*;
+
+#[test]
+fn filters_can_be_reloaded_from_other_threads() {
+    let sink = RecordingSink::default();
+    set_sink(sink.clone());
+
+    let handle = reload_handle();
+    handle.reload(filter::parse("off").unwrap());
+
+    warning!("filtered out");
+    assert_eq!(sink.events().len(), 0);
+
+    // Raise the verbosity from another thread, while this thread waits.
+    let barrier = Arc::new(Barrier::new(2));
+    let reloader = std::thread::spawn({
+        let barrier = barrier.clone();
+
+        move || {
+            handle.reload(filter::parse("reload=warn").unwrap());
+            barrier.wait();
+        }
+    });
+
+    barrier.wait();
+    warning!("emitted after reload");
+    info!("still filtered out");
+    assert_eq!(sink.events().len(), 1);
+
+    reloader.join().unwrap();
+
+    // Reload from a file, ignoring comments and trailing commas, and joining lines.
+    let path = std::env::temp_dir().join(format!("libftrace-reload-{}.txt", std::process::id()));
+    std::fs::write(&path, "# raise verbosity\nerror,\n\n  reload=info, \n").unwrap();
+
+    handle.reload_from_file(&path).unwrap();
+    info!("emitted after reloading from file");
+    assert_eq!(sink.events().len(), 2);
+
+    // Malformed filters are rejected, keeping the current filter. Errors point
+    // to the line and column within the file.
+    std::fs::write(&path, "warn\n  reload=verbose\n").unwrap();
+
+    let err = handle.reload_from_file(&path).unwrap_err();
+    assert!(
+        matches!(err, ReloadError::Parse { line: 2, column: 10, .. }),
+        "{err:?}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid filter directive `reload=verbose` at line 2, column 10: unknown level `verbose`, \
+         expected one of `trace`, `debug`, `info`, `warn`, `error` or `off`"
+    );
+
+    info!("still emitted with the previous filter");
+    assert_eq!(sink.events().len(), 3);
+
+    std::fs::remove_file(&path).unwrap();
+
+    let err = handle.reload_from_file(&path).unwrap_err();
+    assert!(matches!(err, ReloadError::Io(_)), "{err}");
+
+    // Reload from an environment variable, which is only read by this test.
+    let env_name = "LIBFTRACE_RELOAD_TEST";
+
+    // SAFETY: no other test reads or writes the variable.
+    unsafe { std::env::set_var(env_name, "off") };
+    handle.reload_from_env(env_name).unwrap();
+
+    info!("filtered out after reloading from the environment");
+    assert_eq!(sink.events().len(), 3);
+
+    unsafe { std::env::set_var(env_name, "reload=verbose") };
+    assert!(handle.reload_from_env(env_name).is_err());
+
+    info!("still filtered out with the previous filter");
+    assert_eq!(sink.events().len(), 3);
+
+    unsafe { std::env::set_var(env_name, "reload=info") };
+    handle.reload_from_env(env_name).unwrap();
+
+    info!("emitted after reloading from the environment");
+    assert_eq!(sink.events().len(), 4);
+
+    unsafe { std::env::remove_var(env_name) };
+}
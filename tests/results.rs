@@ -0,0 +1,91 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::ops::ControlFlow;
+
+use common::RecordingSink;
+use libftrace::*;
+
+#[traced(level = Info, err(level = Warn, Display))]
+fn lookup(key: &str) -> Result<u32, String> {
+    Err(format!("{key} not found"))
+}
+
+#[traced(level = Info, ret(level = Warn))]
+fn compute() -> u32 {
+    42
+}
+
+#[traced(level = Trace, ret(level = Error))]
+fn answer() -> u32 {
+    7
+}
+
+#[traced(err(level = Warn))]
+fn cache_get(hit: bool) -> Option<u32> {
+    hit.then_some(1)
+}
+
+#[traced(err, ret(Display))]
+fn step(n: u32) -> ControlFlow<&'static str, u32> {
+    if n > 1 {
+        ControlFlow::Break("done")
+    } else {
+        ControlFlow::Continue(n + 1)
+    }
+}
+
+#[test]
+fn results_are_emitted_at_configured_levels() {
+    let sink = RecordingSink::default();
+
+    set_sink(sink.clone());
+    set_filter(filter::parse("trace").unwrap());
+
+    assert_eq!(lookup("user"), Err(String::from("user not found")));
+    assert_eq!(compute(), 42);
+    assert_eq!(cache_get(true), Some(1));
+    assert_eq!(cache_get(false), None);
+    assert_eq!(step(1), ControlFlow::Continue(2));
+    assert_eq!(step(2), ControlFlow::Break("done"));
+
+    let str = |value: &str| Value::Str(String::from(value));
+
+    let events = sink.map_events(|event| (event.level, event.message.clone(), event.fields.clone()));
+
+    assert_eq!(events, vec![
+        (Level::Warn, String::from("results::lookup"), vec![(
+            "error",
+            str("user not found")
+        )]),
+        (Level::Warn, String::from("results::compute"), vec![(
+            "ret",
+            Value::U64(42)
+        )]),
+        (Level::Warn, String::from("results::cache_get"), vec![(
+            "miss",
+            Value::Bool(true)
+        )]),
+        (Level::Info, String::from("results::step"), vec![("ret", Value::U64(2))]),
+        (Level::Error, String::from("results::step"), vec![(
+            "break_value",
+            str("done")
+        )]),
+    ]);
+
+    // Return values are emitted at their own level, even if the span is disabled.
+    sink.clear();
+    set_filter(filter::parse("error").unwrap());
+
+    assert_eq!(answer(), 7);
+    assert!(sink.entered().is_empty());
+
+    let events = sink.map_events(|event| (event.level, event.message.clone(), event.fields.clone()));
+
+    assert_eq!(events, vec![(
+        Level::Error,
+        String::from("results::answer"),
+        vec![("ret", Value::U64(7))]
+    )]);
+}
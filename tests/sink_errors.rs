@@ -0,0 +1,37 @@
+#![cfg(feature = "enabled")]
+
+use std::io::Write;
+
+use libftrace::*;
+
+/// Writer which fails every write, like `stdout` does once it has been closed.
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+#[traced(level = Info, fields(id))]
+fn failing_span(id: usize) {
+    info!("inside failing span", id = id);
+}
+
+#[test]
+fn sink_errors_are_counted_instead_of_panicking() {
+    set_sink(PrettySink::new(ClosedPipe));
+    add_sink(JsonSink::new(ClosedPipe));
+    set_error_handler(ErrorHandler::Count);
+
+    for id in 0..10 {
+        failing_span(id);
+    }
+
+    // Each span is entered, emits an event and is exited, once for each sink.
+    assert_eq!(with_subscriber(|s| s.error_count()), 10 * 3 * 2);
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use common::RecordingSink;
+use libftrace::*;
+
+/// Returns the name of the parent span of every event, along with the message.
+fn events(sink: &RecordingSink) -> Vec<(Option<&'static str>, String)> {
+    sink.events()
+        .iter()
+        .map(|event| (event.parent, event.message.clone()))
+        .collect()
+}
+
+#[test]
+fn spans_can_be_created_and_reentered() {
+    let sink = RecordingSink::default();
+
+    set_sink(sink.clone());
+    set_filter(filter::parse("warn,db=info").unwrap());
+
+    let batch = span!(Level::Warn, "batch", size = 2);
+    let metadata = batch.metadata().unwrap();
+
+    assert_eq!(metadata.target, "spans");
+    assert_eq!(metadata.fields().collect::<Vec<_>>(), vec![("size", &Value::I64(2))]);
+
+    for item in 0..2 {
+        let result = batch.in_scope(|| {
+            warning!("processing item", item = item);
+            item * 2
+        });
+
+        assert_eq!(result, item * 2);
+    }
+
+    warning!("outside of span");
+
+    // Stored spans can be entered again later.
+    {
+        let _entered = batch.enter();
+        warning!("entered again");
+    }
+
+    // Spans are filtered by their target, rather than their name.
+    assert!(span!(Level::Info, "filtered").is_disabled());
+
+    let query = span!(target: "db", Level::Info, "query");
+    assert!(!query.is_disabled());
+    query.in_scope(|| warning!("running query"));
+
+    assert_eq!(events(&sink), vec![
+        (Some("batch"), String::from("processing item")),
+        (Some("batch"), String::from("processing item")),
+        (None, String::from("outside of span")),
+        (Some("batch"), String::from("entered again")),
+        (Some("query"), String::from("running query")),
+    ]);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "enabled")]
+
+mod common;
+
+use std::sync::{Arc, Barrier};
+
+use common::RecordingSink;
+use libftrace::*;
+
+const THREADS: usize = 16;
+const ITERATIONS: usize = 50;
+const MAX_DEPTH: usize = 4;
+
+fn nested(depth: usize) {
+    if depth == MAX_DEPTH {
+        return;
+    }
+
+    let _guard = with_subscriber(|s| s.enter_span(SpanMetadata::new("threads::nested", Level::Error)));
+    assert_eq!(with_subscriber(|s| s.depth()), depth + 1);
+
+    event!(level: Level::Error, "entered span", depth = depth);
+    nested(depth + 1);
+
+    assert_eq!(with_subscriber(|s| s.depth()), depth + 1);
+}
+
+#[test]
+fn span_stacks_are_thread_local() {
+    let sink = RecordingSink::default();
+
+    set_filter(parse("error").unwrap());
+    set_sink(sink.clone());
+
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let handles = (0..THREADS)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+
+            std::thread::spawn(move || {
+                barrier.wait();
+
+                for _ in 0..ITERATIONS {
+                    assert_eq!(with_subscriber(|s| s.depth()), 0);
+                    nested(0);
+                    assert_eq!(with_subscriber(|s| s.depth()), 0);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(with_subscriber(|s| s.depth()), 0);
+
+    let expected = THREADS * ITERATIONS * MAX_DEPTH;
+    assert_eq!(sink.entered().len(), expected);
+    assert_eq!(sink.exited().len(), expected);
+    assert_eq!(sink.events().len(), expected);
+
+    // Spans and events only ever see the spans of their own thread.
+    for span in sink.entered().iter().chain(sink.exited().iter()) {
+        assert!(span.depth < MAX_DEPTH);
+        assert_eq!(span.parent.is_some(), span.depth > 0);
+    }
+
+    for event in sink.events().iter() {
+        assert!(event.depth > 0 && event.depth <= MAX_DEPTH);
+        assert!(event.parent.is_some());
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt::{Debug, Display};
+
+use libftrace::__private::{DebugValue, DisplayValue};
+use libftrace::{ToValue, Value};
+
+/// Error which optionally wraps another error as its source.
+#[derive(Debug)]
+struct Error {
+    message: &'static str,
+    source: Option<Box<Error>>,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|err| err as _)
+    }
+}
+
+/// Type which implements both `Display` and `Debug`, but not `ToValue`.
+#[derive(Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// Converts the value like the event macros do. This must be a macro, since
+/// the conversion is chosen from the concrete type of the value.
+macro_rules! via_display {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use libftrace::__private::{ViaDisplay as _, ViaToValue as _};
+
+        (&DisplayValue(&$value)).__value()
+    }};
+}
+
+/// Converts the value like `#[traced]` does.
+macro_rules! via_debug {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use libftrace::__private::{ViaDebug as _, ViaToValue as _};
+
+        (&DebugValue(&$value)).__value()
+    }};
+}
+
+#[test]
+fn primitives_keep_their_type() {
+    assert_eq!(true.to_value(), Value::Bool(true));
+    assert_eq!((-8i8).to_value(), Value::I64(-8));
+    assert_eq!(isize::MIN.to_value(), Value::I64(i64::MIN));
+    assert_eq!(7u16.to_value(), Value::U64(7));
+    assert_eq!(u64::MAX.to_value(), Value::U64(u64::MAX));
+    assert_eq!(1.5f32.to_value(), Value::F64(1.5));
+    assert_eq!('x'.to_value(), Value::Str(String::from("x")));
+    assert_eq!("str".to_value(), Value::Str(String::from("str")));
+    assert_eq!(String::from("owned").to_value(), Value::Str(String::from("owned")));
+
+    // References are converted like the value they point to.
+    assert_eq!((&&42u32).to_value(), Value::U64(42));
+    assert_eq!(Value::F64(0.25).to_value(), Value::F64(0.25));
+
+    assert_eq!(Value::from(-1i32), Value::I64(-1));
+    assert_eq!(Value::from("from"), Value::Str(String::from("from")));
+}
+
+#[test]
+fn values_are_displayed_without_quotes() {
+    assert_eq!(Value::U64(3).to_string(), "3");
+    assert_eq!(Value::F64(0.5).to_string(), "0.5");
+    assert_eq!(Value::Str(String::from("text")).to_string(), "text");
+    assert_eq!(Value::debug("text").to_string(), "\"text\"");
+    assert_eq!(Value::display(&Point { x: 1, y: 2 }).to_string(), "(1, 2)");
+
+    assert_eq!(Value::Bool(true).as_str(), None);
+    assert_eq!(
+        Value::debug(&Point { x: 1, y: 2 }).as_str(),
+        Some("Point { x: 1, y: 2 }")
+    );
+}
+
+#[test]
+fn errors_include_all_sources() {
+    let err = Error {
+        message: "failed to load config",
+        source: Some(Box::new(Error {
+            message: "failed to read file",
+            source: Some(Box::new(Error {
+                message: "permission denied",
+                source: None,
+            })),
+        })),
+    };
+
+    assert_eq!(
+        Value::error(&err),
+        Value::Error(String::from(
+            "failed to load config: failed to read file: permission denied"
+        ))
+    );
+
+    let leaf = Error {
+        message: "not found",
+        source: None,
+    };
+
+    assert_eq!(Value::error(&leaf), Value::Error(String::from("not found")));
+}
+
+#[test]
+fn conversions_prefer_to_value_over_formatting() {
+    let point = Point { x: 1, y: -2 };
+
+    assert_eq!(via_display!(42u8), Value::U64(42));
+    assert_eq!(via_display!("text"), Value::Str(String::from("text")));
+    assert_eq!(via_display!(point), Value::Display(String::from("(1, -2)")));
+
+    assert_eq!(via_debug!(-1i64), Value::I64(-1));
+    assert_eq!(via_debug!(String::from("text")), Value::Str(String::from("text")));
+    assert_eq!(via_debug!(point), Value::Debug(String::from("Point { x: 1, y: -2 }")));
+    assert_eq!(via_debug!(Some(1)), Value::Debug(String::from("Some(1)")));
+}